@@ -9,8 +9,57 @@ fn main() {
 			println!("cargo:rustc-cfg=ZMQ_HAS_{}=\"1\"", has.to_uppercase());
 		}
 	}
+
+    probe_version();
+    probe_draft();
+}
+
+/// Emit `ZMQ_VERSION_4_x` for each minor release of libzmq 4 that the
+/// linked library is at least as new as.
+///
+/// This is used to gate socket options introduced in later releases.
+fn probe_version() {
+    let mut major = 0;
+    let mut minor = 0;
+    let mut _patch = 0;
+    unsafe { zmq::zmq_version(&mut major, &mut minor, &mut _patch) };
+    for &since in &[1, 2, 3] {
+        if major > 4 || (major == 4 && minor >= since) {
+            println!("cargo:rustc-cfg=ZMQ_VERSION_4_{}=\"1\"", since);
+        }
+    }
+}
+
+/// Emit `ZMQ_HAS_DRAFT` if the draft API is requested and available.
+///
+/// Draft socket types can only be created if libzmq was built with
+/// the draft API enabled, so probe for that by creating one.
+#[cfg(feature = "draft")]
+fn probe_draft() {
+    use std::os::raw::c_int;
+
+    const ZMQ_SERVER: c_int = 12;
+
+    unsafe {
+        let ctx = zmq::zmq_ctx_new();
+        assert!(!ctx.is_null());
+
+        let sock = zmq::zmq_socket(ctx, ZMQ_SERVER);
+        if sock.is_null() {
+            assert!(zmq::zmq_errno() == zmq::errno::EINVAL);
+            println!("cargo:warning=the `draft` feature is enabled, but libzmq \
+                      was built without the draft API");
+        } else {
+            zmq::zmq_close(sock);
+            println!("cargo:rustc-cfg=ZMQ_HAS_DRAFT=\"1\"");
+        }
+        zmq::zmq_ctx_term(ctx);
+    }
 }
 
+#[cfg(not(feature = "draft"))]
+fn probe_draft() {}
+
 #[cfg(not(feature = "zmq_has"))]
 fn main() {
     use std::mem::size_of;
@@ -53,4 +102,7 @@ fn main() {
             println!("cargo:rust-cfg=ZMQ_HAS_ZMQ_HAS=\"1\"");
         }
     }
+
+    probe_version();
+    probe_draft();
 }
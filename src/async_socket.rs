@@ -0,0 +1,355 @@
+//! Integration with the tokio reactor, enabled by the `async` feature.
+
+use libc::{c_int, EIO};
+
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::io::Interest;
+use tokio::io::unix::AsyncFd;
+
+use zmq_sys;
+
+use super::{msg_ptr, Error, Message, PollEvents, Result, Socket, DONTWAIT, POLLIN, POLLOUT,
+            SNDMORE};
+
+// The descriptor is owned by libzmq; this only lends it to the reactor.
+struct SocketFd(RawFd);
+
+impl AsRawFd for SocketFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+/// A socket driven by the tokio reactor.
+///
+/// The socket's `ZMQ_FD` descriptor is registered with the reactor,
+/// so a task waiting for a message does not occupy a thread. As
+/// described for `Socket::get_fd()`, that descriptor is
+/// edge-triggered and only indicates that `ZMQ_EVENTS` may have
+/// changed; `AsyncSocket` takes care of always attempting the
+/// operation first, and of consulting `ZMQ_EVENTS` before going back
+/// to sleep.
+///
+/// The futures returned by `send()`, `send_multipart()`, `recv_msg()`
+/// and `recv_multipart()` are cancellation safe: if one is dropped
+/// before completion, no message (or part of a multipart message) has
+/// been sent or received. Operations borrow the socket mutably, so at
+/// most one is in progress at a time.
+///
+/// `AsyncSocket` also implements `Stream`, yielding received
+/// multipart messages, and `Sink`, sending them, for use with the
+/// combinators of the `futures` crate. The stream ends after an error
+/// for which `Error::is_terminal()` holds.
+///
+/// Creating an `AsyncSocket` requires a running tokio runtime with
+/// I/O enabled.
+pub struct AsyncSocket {
+    // Deregister the descriptor before the socket closes it.
+    fd: AsyncFd<SocketFd>,
+    socket: Socket,
+    // The message being sent through the `Sink` implementation.
+    pending: Vec<Message>,
+    terminated: bool,
+}
+
+/// A multipart message, as yielded by the `Stream` and accepted by the
+/// `Sink` implementation of `AsyncSocket`.
+pub type Multipart = Vec<Vec<u8>>;
+
+fn io_to_error(error: io::Error) -> Error {
+    // Errors without an OS error code, e.g. when the reactor is gone,
+    // are reported as `EIO`; `ETERM` would misleadingly suggest that
+    // the context was terminated.
+    Error::from_raw(error.raw_os_error().unwrap_or(EIO))
+}
+
+// Send a message, leaving it in place if sending fails.
+fn send_msg(socket: &Socket, msg: &mut Message, flags: i32) -> Result<()> {
+    zmq_try!(unsafe { zmq_sys::zmq_msg_send(msg_ptr(msg), socket.sock, flags as c_int) });
+    Ok(())
+}
+
+impl AsyncSocket {
+    /// Register a socket with the reactor of the current tokio runtime.
+    pub fn new(socket: Socket) -> Result<AsyncSocket> {
+        let fd = try!(socket.get_fd());
+        let fd = try!(AsyncFd::with_interest(SocketFd(fd), Interest::READABLE)
+                          .map_err(io_to_error));
+        Ok(AsyncSocket {
+            fd: fd,
+            socket: socket,
+            pending: Vec::new(),
+            terminated: false,
+        })
+    }
+
+    /// Return a reference to the underlying socket, e.g. for binding,
+    /// connecting or setting options.
+    ///
+    /// Sending or receiving through this reference is allowed, but
+    /// only with `DONTWAIT`, as blocking would stall the reactor.
+    pub fn get_ref(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Deregister the socket from the reactor and return it.
+    pub fn into_inner(self) -> Socket {
+        self.socket
+    }
+
+    /// Send a message; see `Socket::send()`.
+    ///
+    /// `DONTWAIT` is implied; the returned future completes once the
+    /// message has been queued.
+    pub fn send<'a, T>(&'a mut self, data: T, flags: i32) -> SendFuture<'a>
+        where T: Into<Message>
+    {
+        SendFuture {
+            socket: self,
+            msg: Some(data.into()),
+            flags: flags,
+        }
+    }
+
+    /// Send a multipart message; see `Socket::send_multipart()`.
+    ///
+    /// The future waits until the first part can be sent, after which
+    /// libzmq accepts the remaining parts without blocking, so the
+    /// message is never sent partially.
+    pub fn send_multipart<'a, I, T>(&'a mut self, iter: I, flags: i32) -> SendMultipartFuture<'a>
+        where I: IntoIterator<Item = T>,
+              T: Into<Message>
+    {
+        SendMultipartFuture {
+            socket: self,
+            parts: iter.into_iter().map(|part| part.into()).collect(),
+            flags: flags,
+        }
+    }
+
+    /// Receive a message; see `Socket::recv_msg()`.
+    pub fn recv_msg<'a>(&'a mut self, flags: i32) -> RecvMsgFuture<'a> {
+        RecvMsgFuture {
+            socket: self,
+            flags: flags,
+        }
+    }
+
+    /// Receive a multipart message; see `Socket::recv_multipart()`.
+    ///
+    /// Since libzmq delivers multipart messages atomically, all parts
+    /// are received at once when the first one arrives.
+    pub fn recv_multipart<'a>(&'a mut self, flags: i32) -> RecvMultipartFuture<'a> {
+        RecvMultipartFuture {
+            socket: self,
+            flags: flags,
+        }
+    }
+
+    // Attempt `op` until it fails with an error other than `EAGAIN`,
+    // waiting for `events` in between.
+    fn poll_op<T, F>(&mut self, cx: &mut task::Context, events: PollEvents, mut op: F) -> Poll<Result<T>>
+        where F: FnMut(&Socket) -> Result<T>
+    {
+        loop {
+            match op(&self.socket) {
+                Err(Error::EAGAIN) => {}
+                result => return Poll::Ready(result),
+            }
+            let mut guard = match self.fd.poll_read_ready(cx) {
+                Poll::Ready(Ok(guard)) => guard,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(io_to_error(e))),
+                Poll::Pending => return Poll::Pending,
+            };
+            // Reading `ZMQ_EVENTS` rearms the descriptor, so the
+            // readiness may only be cleared after doing so. If the
+            // socket turns out to be ready, retry without clearing it.
+            match self.socket.get_events() {
+                Ok(ready) if ready.intersects(events) => {}
+                Ok(_) => guard.clear_ready(),
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+
+    // Send `parts` as a multipart message, clearing it once done.
+    fn poll_send_multipart(&mut self, cx: &mut task::Context, parts: &mut Vec<Message>, flags: i32)
+                           -> Poll<Result<()>> {
+        if parts.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+        let last = parts.len() - 1;
+        let more = |i| if i < last { SNDMORE } else { 0 };
+        let flags = flags & !DONTWAIT;
+        {
+            let first = &mut parts[0];
+            let result = self.poll_op(cx, POLLOUT, |socket| {
+                send_msg(socket, first, flags | DONTWAIT | more(0))
+            });
+            match result {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        // The remaining parts are accepted right away, as libzmq does
+        // not apply the high-water mark in the middle of a message.
+        let mut result = Ok(());
+        for (i, part) in parts.iter_mut().enumerate().skip(1) {
+            if let Err(e) = send_msg(&self.socket, part, flags | more(i)) {
+                result = Err(e);
+                break;
+            }
+        }
+        parts.clear();
+        Poll::Ready(result)
+    }
+
+    fn poll_recv_multipart(&mut self, cx: &mut task::Context, flags: i32) -> Poll<Result<Multipart>> {
+        let flags = flags & !DONTWAIT;
+        self.poll_op(cx, POLLIN, |socket| {
+            let mut parts = vec![try!(socket.recv_bytes(flags | DONTWAIT))];
+            while try!(socket.get_rcvmore()) {
+                parts.push(try!(socket.recv_bytes(flags)));
+            }
+            Ok(parts)
+        })
+    }
+}
+
+/// Future returned by `AsyncSocket::send()`.
+pub struct SendFuture<'a> {
+    socket: &'a mut AsyncSocket,
+    msg: Option<Message>,
+    flags: i32,
+}
+
+impl<'a> Future for SendFuture<'a> {
+    type Output = Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<()>> {
+        let this = &mut *self;
+        let flags = this.flags | DONTWAIT;
+        let result = {
+            let msg = this.msg.as_mut().expect("future polled after completion");
+            match this.socket.poll_op(cx, POLLOUT, |socket| send_msg(socket, msg, flags)) {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            }
+        };
+        this.msg = None;
+        Poll::Ready(result)
+    }
+}
+
+/// Future returned by `AsyncSocket::send_multipart()`.
+pub struct SendMultipartFuture<'a> {
+    socket: &'a mut AsyncSocket,
+    parts: Vec<Message>,
+    flags: i32,
+}
+
+impl<'a> Future for SendMultipartFuture<'a> {
+    type Output = Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<()>> {
+        let this = &mut *self;
+        this.socket.poll_send_multipart(cx, &mut this.parts, this.flags)
+    }
+}
+
+/// Future returned by `AsyncSocket::recv_msg()`.
+pub struct RecvMsgFuture<'a> {
+    socket: &'a mut AsyncSocket,
+    flags: i32,
+}
+
+impl<'a> Future for RecvMsgFuture<'a> {
+    type Output = Result<Message>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<Message>> {
+        let flags = self.flags | DONTWAIT;
+        self.socket.poll_op(cx, POLLIN, |socket| socket.recv_msg(flags))
+    }
+}
+
+/// Future returned by `AsyncSocket::recv_multipart()`.
+pub struct RecvMultipartFuture<'a> {
+    socket: &'a mut AsyncSocket,
+    flags: i32,
+}
+
+impl<'a> Future for RecvMultipartFuture<'a> {
+    type Output = Result<Multipart>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<Multipart>> {
+        let flags = self.flags;
+        self.socket.poll_recv_multipart(cx, flags)
+    }
+}
+
+impl Stream for AsyncSocket {
+    type Item = Result<Multipart>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Option<Result<Multipart>>> {
+        if self.terminated {
+            return Poll::Ready(None);
+        }
+        match self.poll_recv_multipart(cx, 0) {
+            Poll::Ready(Err(e)) => {
+                self.terminated = e.is_terminal();
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(Ok(parts)) => Poll::Ready(Some(Ok(parts))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Sends multipart messages, one at a time.
+///
+/// If sending a message fails, it is dropped, and the error is
+/// returned from `poll_flush()` (or `poll_ready()` or `poll_close()`).
+/// Since libzmq only refuses the first part if the message cannot be
+/// queued, this normally means nothing was sent; a failure on a later
+/// part, though, may leave the message partially queued.
+impl Sink<Multipart> for AsyncSocket {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<()>> {
+        self.poll_flush(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Multipart) -> Result<()> {
+        // `poll_ready()` must have completed since the last message.
+        if !self.pending.is_empty() {
+            return Err(Error::EFSM);
+        }
+        self.pending = item.into_iter().map(Message::from).collect();
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<()>> {
+        let this = &mut *self;
+        let mut pending = mem::replace(&mut this.pending, Vec::new());
+        let result = this.poll_send_multipart(cx, &mut pending, 0);
+        // A message that failed to be sent is dropped, like one that was
+        // sent successfully.
+        if let Poll::Pending = result {
+            this.pending = pending;
+        }
+        result
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Result<()>> {
+        self.poll_flush(cx)
+    }
+}
@@ -0,0 +1,288 @@
+//! Structured endpoint addresses.
+
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::Error;
+
+/// The port of a TCP, UDP or VMCI endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Port {
+    /// The wildcard port (`*`), only valid for `bind()`; the system
+    /// assigns an ephemeral port.
+    Wildcard,
+    Number(u16),
+}
+
+/// The address of an IPC endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IpcPath {
+    /// A filesystem path.
+    Path(String),
+    /// A name in the Linux abstract socket namespace, written as
+    /// `ipc://@name`.
+    Abstract(String),
+    /// The wildcard path (`*`), only valid for `bind()`; a unique
+    /// temporary path is chosen.
+    Wildcard,
+}
+
+/// A 0MQ endpoint, consisting of a transport and an address.
+///
+/// `Endpoint` can be parsed from and formatted to the string form
+/// used by libzmq, e.g. `"tcp://127.0.0.1:5555"`, and can be passed
+/// to `Socket::bind()`, `Socket::connect()` and friends.
+///
+/// Parsing validates the syntax of the address, but does not resolve
+/// host or interface names.
+///
+/// ```
+/// let endpoint: zmq::Endpoint = "tcp://eth0;192.168.1.1:5555".parse().unwrap();
+/// assert_eq!(endpoint, zmq::Endpoint::Tcp {
+///     host: "192.168.1.1".to_owned(),
+///     port: zmq::Port::Number(5555),
+///     source: Some("eth0".to_owned()),
+/// });
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// `tcp://[source;]host:port`, where `host` is an interface, a
+    /// host name or an IP address (with IPv6 addresses enclosed in
+    /// brackets), or `*` for all interfaces.
+    Tcp { host: String, port: Port, source: Option<String> },
+    /// `ipc://path`
+    Ipc { path: IpcPath },
+    /// `inproc://name`
+    Inproc { name: String },
+    /// `pgm://interface;address:port`
+    Pgm { interface: String, address: String, port: u16 },
+    /// `epgm://interface;address:port`
+    Epgm { interface: String, address: String, port: u16 },
+    /// `tipc://address`, where the address is kept verbatim.
+    Tipc { address: String },
+    /// `vmci://address:port`
+    Vmci { address: String, port: Port },
+    /// `udp://[source;]host:port`, for `RADIO` and `DISH` sockets.
+    Udp { host: String, port: Port, source: Option<String> },
+}
+
+/// Error returned when parsing an `Endpoint` fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseEndpointError {
+    /// The `transport://` prefix is missing.
+    MissingTransport,
+    /// The transport is not known.
+    UnknownTransport(String),
+    /// The address part is malformed.
+    InvalidAddress,
+    /// The port is neither a number in the range of 0 to 65535, nor
+    /// (where allowed) the wildcard `*`.
+    InvalidPort,
+    /// The endpoint contains a NUL byte.
+    NulByte,
+}
+
+impl fmt::Display for ParseEndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseEndpointError::MissingTransport => write!(f, "missing transport"),
+            ParseEndpointError::UnknownTransport(ref t) => write!(f, "unknown transport `{}`", t),
+            ParseEndpointError::InvalidAddress => write!(f, "invalid address"),
+            ParseEndpointError::InvalidPort => write!(f, "invalid port"),
+            ParseEndpointError::NulByte => write!(f, "endpoint contains a NUL byte"),
+        }
+    }
+}
+
+impl error::Error for ParseEndpointError {
+    fn description(&self) -> &str {
+        match *self {
+            ParseEndpointError::MissingTransport => "missing transport",
+            ParseEndpointError::UnknownTransport(_) => "unknown transport",
+            ParseEndpointError::InvalidAddress => "invalid address",
+            ParseEndpointError::InvalidPort => "invalid port",
+            ParseEndpointError::NulByte => "endpoint contains a NUL byte",
+        }
+    }
+}
+
+impl From<ParseEndpointError> for Error {
+    fn from(_: ParseEndpointError) -> Self {
+        Error::EINVAL
+    }
+}
+
+fn parse_port(s: &str, wildcard: bool) -> Result<Port, ParseEndpointError> {
+    if wildcard && s == "*" {
+        return Ok(Port::Wildcard);
+    }
+    s.parse().map(Port::Number).map_err(|_| ParseEndpointError::InvalidPort)
+}
+
+// Split `[source;]host:port`, with `host` possibly being a bracketed
+// IPv6 address.
+fn parse_host_port(s: &str) -> Result<(Option<String>, String, Port), ParseEndpointError> {
+    let (source, dest) = match s.find(';') {
+        Some(pos) => (Some(&s[..pos]), &s[pos + 1..]),
+        None => (None, s),
+    };
+    if source == Some("") {
+        return Err(ParseEndpointError::InvalidAddress);
+    }
+    let pos = try!(dest.rfind(':').ok_or(ParseEndpointError::InvalidAddress));
+    let (host, port) = (&dest[..pos], &dest[pos + 1..]);
+    if host.is_empty() || (host.starts_with('[') != host.ends_with(']')) {
+        return Err(ParseEndpointError::InvalidAddress);
+    }
+    let port = try!(parse_port(port, true));
+    Ok((source.map(|s| s.to_owned()), host.to_owned(), port))
+}
+
+fn parse_pgm(s: &str) -> Result<(String, String, u16), ParseEndpointError> {
+    let pos = try!(s.find(';').ok_or(ParseEndpointError::InvalidAddress));
+    let (interface, dest) = (&s[..pos], &s[pos + 1..]);
+    let pos = try!(dest.rfind(':').ok_or(ParseEndpointError::InvalidAddress));
+    let (address, port) = (&dest[..pos], &dest[pos + 1..]);
+    if interface.is_empty() || address.is_empty() {
+        return Err(ParseEndpointError::InvalidAddress);
+    }
+    match try!(parse_port(port, false)) {
+        Port::Number(port) => Ok((interface.to_owned(), address.to_owned(), port)),
+        Port::Wildcard => Err(ParseEndpointError::InvalidPort),
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = ParseEndpointError;
+
+    fn from_str(s: &str) -> Result<Endpoint, ParseEndpointError> {
+        if s.contains('\0') {
+            return Err(ParseEndpointError::NulByte);
+        }
+        let pos = try!(s.find("://").ok_or(ParseEndpointError::MissingTransport));
+        let (transport, address) = (&s[..pos], &s[pos + 3..]);
+        if address.is_empty() {
+            return Err(ParseEndpointError::InvalidAddress);
+        }
+        let endpoint = match transport {
+            "tcp" => {
+                let (source, host, port) = try!(parse_host_port(address));
+                Endpoint::Tcp { host: host, port: port, source: source }
+            }
+            "udp" => {
+                let (source, host, port) = try!(parse_host_port(address));
+                Endpoint::Udp { host: host, port: port, source: source }
+            }
+            "ipc" => {
+                let path = if address == "*" {
+                    IpcPath::Wildcard
+                } else if address.starts_with('@') {
+                    if address.len() == 1 {
+                        return Err(ParseEndpointError::InvalidAddress);
+                    }
+                    IpcPath::Abstract(address[1..].to_owned())
+                } else {
+                    IpcPath::Path(address.to_owned())
+                };
+                Endpoint::Ipc { path: path }
+            }
+            "inproc" => Endpoint::Inproc { name: address.to_owned() },
+            "pgm" => {
+                let (interface, address, port) = try!(parse_pgm(address));
+                Endpoint::Pgm { interface: interface, address: address, port: port }
+            }
+            "epgm" => {
+                let (interface, address, port) = try!(parse_pgm(address));
+                Endpoint::Epgm { interface: interface, address: address, port: port }
+            }
+            "tipc" => Endpoint::Tipc { address: address.to_owned() },
+            "vmci" => {
+                let pos = try!(address.rfind(':').ok_or(ParseEndpointError::InvalidAddress));
+                if pos == 0 {
+                    return Err(ParseEndpointError::InvalidAddress);
+                }
+                let port = try!(parse_port(&address[pos + 1..], true));
+                Endpoint::Vmci { address: address[..pos].to_owned(), port: port }
+            }
+            "" => return Err(ParseEndpointError::MissingTransport),
+            _ => return Err(ParseEndpointError::UnknownTransport(transport.to_owned())),
+        };
+        Ok(endpoint)
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Port::Wildcard => write!(f, "*"),
+            Port::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Endpoint::Tcp { ref host, port, ref source } |
+            Endpoint::Udp { ref host, port, ref source } => {
+                let transport = match *self {
+                    Endpoint::Tcp { .. } => "tcp",
+                    _ => "udp",
+                };
+                try!(write!(f, "{}://", transport));
+                if let Some(ref source) = *source {
+                    try!(write!(f, "{};", source));
+                }
+                write!(f, "{}:{}", host, port)
+            }
+            Endpoint::Ipc { path: IpcPath::Path(ref path) } => write!(f, "ipc://{}", path),
+            Endpoint::Ipc { path: IpcPath::Abstract(ref name) } => write!(f, "ipc://@{}", name),
+            Endpoint::Ipc { path: IpcPath::Wildcard } => write!(f, "ipc://*"),
+            Endpoint::Inproc { ref name } => write!(f, "inproc://{}", name),
+            Endpoint::Pgm { ref interface, ref address, port } => {
+                write!(f, "pgm://{};{}:{}", interface, address, port)
+            }
+            Endpoint::Epgm { ref interface, ref address, port } => {
+                write!(f, "epgm://{};{}:{}", interface, address, port)
+            }
+            Endpoint::Tipc { ref address } => write!(f, "tipc://{}", address),
+            Endpoint::Vmci { ref address, port } => write!(f, "vmci://{}:{}", address, port),
+        }
+    }
+}
+
+/// A value that can be passed as an endpoint to `Socket::bind()`,
+/// `Socket::connect()`, `Socket::unbind()` and `Socket::disconnect()`.
+///
+/// This is implemented for strings, which are passed to libzmq as-is,
+/// and for `Endpoint`.
+pub trait AsEndpoint {
+    /// Return the string form of the endpoint.
+    fn as_endpoint(&self) -> Cow<str>;
+}
+
+impl AsEndpoint for str {
+    fn as_endpoint(&self) -> Cow<str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl AsEndpoint for String {
+    fn as_endpoint(&self) -> Cow<str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl AsEndpoint for Endpoint {
+    fn as_endpoint(&self) -> Cow<str> {
+        Cow::Owned(self.to_string())
+    }
+}
+
+impl<'a, T: AsEndpoint + ?Sized> AsEndpoint for &'a T {
+    fn as_endpoint(&self) -> Cow<str> {
+        (**self).as_endpoint()
+    }
+}
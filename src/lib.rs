@@ -13,16 +13,28 @@ extern crate log;
 
 extern crate libc;
 extern crate zmq_sys;
+#[cfg(all(feature = "async", unix))]
+extern crate futures_core;
+#[cfg(all(feature = "async", unix))]
+extern crate futures_sink;
+#[cfg(all(feature = "async", unix))]
+extern crate tokio;
+#[cfg(all(feature = "mio", unix))]
+extern crate mio;
 
 use libc::{c_int, c_long, c_short};
 use std::ffi;
 use std::fmt;
 use std::marker::PhantomData;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::ops::{Deref, DerefMut};
 use std::os::raw::c_void;
 use std::result;
 use std::string::FromUtf8Error;
 use std::{mem, ptr, str};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
 
 use zmq_sys::{errno, RawFd};
 
@@ -37,16 +49,59 @@ macro_rules! zmq_try {
 }
 
 mod sockopt;
+mod endpoint;
 mod message;
+mod monitor;
+mod proxy;
+mod poller;
+mod timers;
+mod options;
+mod typed;
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+mod thread_safe;
+#[cfg(all(feature = "async", unix))]
+mod async_socket;
+#[cfg(all(feature = "mio", unix))]
+mod readiness;
 
 pub use SocketType::*;
-pub use message::Message;
+pub use endpoint::{AsEndpoint, Endpoint, IpcPath, ParseEndpointError, Port};
+pub use message::{Message, Properties};
+pub use message::{PROPERTY_SOCKET_TYPE, PROPERTY_IDENTITY, PROPERTY_ROUTING_ID,
+                  PROPERTY_USER_ID, PROPERTY_PEER_ADDRESS};
+pub use monitor::{Monitor, MonitorEvents, SocketEvent};
+pub use proxy::{spawn_proxy, ProxyControl, ProxyStatistics};
+pub use poller::{Poller, PollerEvents};
+pub use timers::Timers;
+pub use options::{SocketOptions, SocketOptionError};
+pub use typed::{TypedSocket, TransitionError, TransitionResult, SocketKind, CanSend, CanRecv};
+pub use typed::{Pair, Pub, Sub, Req, ReqAwaiting, Rep, RepReplying, Dealer, Router, Pull, Push,
+                XPub, XSub, Stream};
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+pub use thread_safe::ThreadSafeSocket;
+#[cfg(all(feature = "async", unix))]
+pub use async_socket::{AsyncSocket, Multipart, SendFuture, SendMultipartFuture,
+                       RecvMsgFuture, RecvMultipartFuture};
+#[cfg(all(feature = "mio", unix))]
+pub use readiness::ReadinessSource;
+pub use monitor::{EVENT_CONNECTED, EVENT_CONNECT_DELAYED, EVENT_CONNECT_RETRIED,
+                  EVENT_LISTENING, EVENT_BIND_FAILED, EVENT_ACCEPTED,
+                  EVENT_ACCEPT_FAILED, EVENT_CLOSED, EVENT_CLOSE_FAILED,
+                  EVENT_DISCONNECTED, EVENT_MONITOR_STOPPED,
+                  EVENT_HANDSHAKE_FAILED_NO_DETAIL, EVENT_HANDSHAKE_SUCCEEDED,
+                  EVENT_HANDSHAKE_FAILED_PROTOCOL, EVENT_HANDSHAKE_FAILED_AUTH,
+                  EVENT_ALL};
 use message::msg_ptr;
 
 /// `zmq`-specific Result type.
 pub type Result<T> = result::Result<T, Error>;
 
 /// Socket types
+///
+/// The draft socket types (`SERVER`, `CLIENT`, `RADIO`, `DISH`,
+/// `GATHER`, `SCATTER`, `DGRAM`, `PEER` and `CHANNEL`) are only
+/// available with the `draft` feature, and require libzmq to be built
+/// with the draft API enabled.
 #[allow(non_camel_case_types)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum SocketType {
@@ -62,6 +117,25 @@ pub enum SocketType {
     XPUB   = 9,
     XSUB   = 10,
     STREAM = 11,
+
+    #[cfg(feature = "draft")]
+    SERVER  = 12,
+    #[cfg(feature = "draft")]
+    CLIENT  = 13,
+    #[cfg(feature = "draft")]
+    RADIO   = 14,
+    #[cfg(feature = "draft")]
+    DISH    = 15,
+    #[cfg(feature = "draft")]
+    GATHER  = 16,
+    #[cfg(feature = "draft")]
+    SCATTER = 17,
+    #[cfg(feature = "draft")]
+    DGRAM   = 18,
+    #[cfg(feature = "draft")]
+    PEER    = 19,
+    #[cfg(feature = "draft")]
+    CHANNEL = 20,
 }
 
 impl Copy for SocketType {}
@@ -131,6 +205,21 @@ enum Constants {
     ZMQ_HANDSHAKE_IVL            = 66,
     ZMQ_SOCKS_PROXY              = 68,
     ZMQ_XPUB_NODROP              = 69,
+    ZMQ_XPUB_MANUAL              = 71,
+    ZMQ_XPUB_WELCOME_MSG         = 72,
+    ZMQ_STREAM_NOTIFY            = 73,
+    ZMQ_INVERT_MATCHING          = 74,
+    ZMQ_HEARTBEAT_IVL            = 75,
+    ZMQ_HEARTBEAT_TTL            = 76,
+    ZMQ_HEARTBEAT_TIMEOUT        = 77,
+    ZMQ_CONNECT_TIMEOUT          = 79,
+    ZMQ_TCP_MAXRT                = 80,
+    ZMQ_MULTICAST_MAXTPDU        = 84,
+    ZMQ_BINDTODEVICE             = 92,
+    ZMQ_ZAP_ENFORCE_DOMAIN       = 93,
+    ZMQ_LOOPBACK_FASTPATH        = 94,
+    ZMQ_METADATA                 = 95,
+    ZMQ_ROUTER_NOTIFY            = 97,
 
     ZMQ_MSG_MORE                 = 1,
     ZMQ_MSG_SHARED               = 128,
@@ -159,45 +248,82 @@ impl Copy for Mechanism {}
 
 
 /// An error returned by a 0MQ API function.
+///
+/// Error codes not covered by the other variants are represented by
+/// `Error::Other`, so converting an error code never fails.
 #[derive(Clone, Eq, PartialEq)]
 pub enum Error {
-    EACCES          = errno::EACCES as isize,
-    EADDRINUSE      = errno::EADDRINUSE as isize,
-    EAGAIN          = errno::EAGAIN as isize,
-    EBUSY           = errno::EBUSY as isize,
-    ECONNREFUSED    = errno::ECONNREFUSED as isize,
-    EFAULT          = errno::EFAULT as isize,
-    EINTR           = errno::EINTR as isize,
-    EHOSTUNREACH    = errno::EHOSTUNREACH as isize,
-    EINPROGRESS     = errno::EINPROGRESS as isize,
-    EINVAL          = errno::EINVAL as isize,
-    EMFILE          = errno::EMFILE as isize,
-    EMSGSIZE        = errno::EMSGSIZE as isize,
-    ENAMETOOLONG    = errno::ENAMETOOLONG as isize,
-    ENODEV          = errno::ENODEV as isize,
-    ENOENT          = errno::ENOENT as isize,
-    ENOMEM          = errno::ENOMEM as isize,
-    ENOTCONN        = errno::ENOTCONN as isize,
-    ENOTSOCK        = errno::ENOTSOCK as isize,
-    EPROTO          = errno::EPROTO as isize,
-    EPROTONOSUPPORT = errno::EPROTONOSUPPORT as isize,
-    ENOTSUP         = errno::ENOTSUP as isize,
-    ENOBUFS         = errno::ENOBUFS as isize,
-    ENETDOWN        = errno::ENETDOWN as isize,
-    EADDRNOTAVAIL   = errno::EADDRNOTAVAIL as isize,
+    EACCES,
+    EADDRINUSE,
+    EAGAIN,
+    EBUSY,
+    ECONNREFUSED,
+    EFAULT,
+    EINTR,
+    EHOSTUNREACH,
+    EINPROGRESS,
+    EINVAL,
+    EMFILE,
+    EMSGSIZE,
+    ENAMETOOLONG,
+    ENODEV,
+    ENOENT,
+    ENOMEM,
+    ENOTCONN,
+    ENOTSOCK,
+    EPROTO,
+    EPROTONOSUPPORT,
+    ENOTSUP,
+    ENOBUFS,
+    ENETDOWN,
+    EADDRNOTAVAIL,
 
     // native zmq error codes
-    EFSM            = errno::EFSM as isize,
-    ENOCOMPATPROTO  = errno::ENOCOMPATPROTO as isize,
-    ETERM           = errno::ETERM as isize,
-    EMTHREAD        = errno::EMTHREAD as isize,
+    EFSM,
+    ENOCOMPATPROTO,
+    ETERM,
+    EMTHREAD,
+
+    /// An error code not known to this crate, as returned by
+    /// `zmq_errno()`.
+    Other(i32),
 }
 
 impl Copy for Error {}
 
 impl Error {
     pub fn to_raw(&self) -> i32 {
-        *self as i32
+        match *self {
+            Error::EACCES          => errno::EACCES,
+            Error::EADDRINUSE      => errno::EADDRINUSE,
+            Error::EAGAIN          => errno::EAGAIN,
+            Error::EBUSY           => errno::EBUSY,
+            Error::ECONNREFUSED    => errno::ECONNREFUSED,
+            Error::EFAULT          => errno::EFAULT,
+            Error::EINTR           => errno::EINTR,
+            Error::EHOSTUNREACH    => errno::EHOSTUNREACH,
+            Error::EINPROGRESS     => errno::EINPROGRESS,
+            Error::EINVAL          => errno::EINVAL,
+            Error::EMFILE          => errno::EMFILE,
+            Error::EMSGSIZE        => errno::EMSGSIZE,
+            Error::ENAMETOOLONG    => errno::ENAMETOOLONG,
+            Error::ENODEV          => errno::ENODEV,
+            Error::ENOENT          => errno::ENOENT,
+            Error::ENOMEM          => errno::ENOMEM,
+            Error::ENOTCONN        => errno::ENOTCONN,
+            Error::ENOTSOCK        => errno::ENOTSOCK,
+            Error::EPROTO          => errno::EPROTO,
+            Error::EPROTONOSUPPORT => errno::EPROTONOSUPPORT,
+            Error::ENOTSUP         => errno::ENOTSUP,
+            Error::ENOBUFS         => errno::ENOBUFS,
+            Error::ENETDOWN        => errno::ENETDOWN,
+            Error::EADDRNOTAVAIL   => errno::EADDRNOTAVAIL,
+            Error::EFSM            => errno::EFSM,
+            Error::ENOCOMPATPROTO  => errno::ENOCOMPATPROTO,
+            Error::ETERM           => errno::ETERM,
+            Error::EMTHREAD        => errno::EMTHREAD,
+            Error::Other(raw)     => raw,
+        }
     }
 
     pub fn from_raw(raw: i32) -> Error {
@@ -240,49 +366,71 @@ impl Error {
             156384765                => Error::ETERM,
             156384766                => Error::EMTHREAD,
 
-            x => {
-                unsafe {
-                    let s = zmq_sys::zmq_strerror(x);
-                    panic!("unknown error [{}]: {}",
-                        x,
-                        str::from_utf8(ffi::CStr::from_ptr(s).to_bytes()).unwrap()
-                    )
-                }
-            }
+            x => Error::Other(x),
+        }
+    }
+
+    /// Return true if the operation may succeed when retried, i.e.
+    /// for `EAGAIN` and `EINTR`.
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            Error::EAGAIN | Error::EINTR => true,
+            _ => false,
+        }
+    }
+
+    /// Return true if the context has been terminated (`ETERM`), after
+    /// which the socket can only be closed.
+    pub fn is_terminal(&self) -> bool {
+        *self == Error::ETERM
+    }
+
+    /// Return true if the operation is not valid in the current state
+    /// of the socket (`EFSM`), e.g. sending twice on a `REQ` socket.
+    pub fn is_state(&self) -> bool {
+        *self == Error::EFSM
+    }
+
+    /// Attach the failing operation, and optionally the endpoint it
+    /// was applied to, to this error.
+    ///
+    /// ```
+    /// let err = zmq::Error::ECONNREFUSED.with_context("connect", Some("tcp://host:5555"));
+    /// assert_eq!(err.to_string(), format!("connect tcp://host:5555: {}", zmq::Error::ECONNREFUSED));
+    /// ```
+    pub fn with_context(self, operation: &'static str, endpoint: Option<&str>) -> ContextError {
+        ContextError {
+            error: self,
+            operation: operation,
+            endpoint: endpoint.map(|e| e.to_owned()),
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        unsafe {
+            let s = zmq_sys::zmq_strerror(self.to_raw());
+            let v: &'static [u8] = mem::transmute(ffi::CStr::from_ptr(s).to_bytes());
+            str::from_utf8(v).unwrap_or("unknown error")
         }
     }
 }
 
 impl std::error::Error for Error {
     fn description(&self) -> &str {
-        unsafe {
-            let s = zmq_sys::zmq_strerror(*self as c_int);
-            let v: &'static [u8] =
-                mem::transmute(ffi::CStr::from_ptr(s).to_bytes());
-            str::from_utf8(v).unwrap()
-        }
+        self.message()
     }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe {
-            let s = zmq_sys::zmq_strerror(*self as c_int);
-            let v: &'static [u8] =
-                mem::transmute(ffi::CStr::from_ptr(s).to_bytes());
-            write!(f, "{}", str::from_utf8(v).unwrap())
-        }
+        write!(f, "{}", self.message())
     }
 }
 
 impl fmt::Debug for Error {
     /// Return the error string for an error.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe {
-            let s = zmq_sys::zmq_strerror(*self as c_int);
-            write!(f, "{}",
-                   str::from_utf8(ffi::CStr::from_ptr(s).to_bytes()).unwrap())
-        }
+        write!(f, "{}", self.message())
     }
 }
 
@@ -310,6 +458,63 @@ impl From<Error> for std::io::Error {
     }
 }
 
+/// An `Error` along with the operation that failed, and the endpoint
+/// it was applied to, if any; see `Error::with_context()`.
+///
+/// This is returned by `Socket::bind()`, `connect()`, `unbind()` and
+/// `disconnect()`, and is displayed as, e.g., `connect
+/// tcp://host:5555: Connection refused`, which makes for more useful
+/// log messages than the bare error. It converts into an `Error`, so
+/// `try!` can still be used in functions returning `zmq::Result`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContextError {
+    pub error: Error,
+    pub operation: &'static str,
+    pub endpoint: Option<String>,
+}
+
+impl ContextError {
+    /// See `Error::is_retryable()`.
+    pub fn is_retryable(&self) -> bool {
+        self.error.is_retryable()
+    }
+
+    /// See `Error::is_terminal()`.
+    pub fn is_terminal(&self) -> bool {
+        self.error.is_terminal()
+    }
+
+    /// See `Error::is_state()`.
+    pub fn is_state(&self) -> bool {
+        self.error.is_state()
+    }
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.endpoint {
+            Some(ref endpoint) => write!(f, "{} {}: {}", self.operation, endpoint, self.error),
+            None => write!(f, "{}: {}", self.operation, self.error),
+        }
+    }
+}
+
+impl std::error::Error for ContextError {
+    fn description(&self) -> &str {
+        self.error.message()
+    }
+
+    fn cause(&self) -> Option<&std::error::Error> {
+        Some(&self.error)
+    }
+}
+
+impl From<ContextError> for Error {
+    fn from(err: ContextError) -> Self {
+        err.error
+    }
+}
+
 fn errno_to_error() -> Error {
     Error::from_raw(unsafe { zmq_sys::zmq_errno() })
 }
@@ -327,13 +532,116 @@ pub fn version() -> (i32, i32, i32) {
     (major as i32, minor as i32, patch as i32)
 }
 
+// Raw 0MQ context option constants.
+const ZMQ_IO_THREADS: c_int = 1;
+const ZMQ_MAX_SOCKETS: c_int = 2;
+const ZMQ_SOCKET_LIMIT: c_int = 3;
+const ZMQ_THREAD_PRIORITY: c_int = 3;
+const ZMQ_THREAD_SCHED_POLICY: c_int = 4;
+const ZMQ_MAX_MSGSZ: c_int = 5;
+const ZMQ_THREAD_AFFINITY_CPU_ADD: c_int = 7;
+const ZMQ_THREAD_AFFINITY_CPU_REMOVE: c_int = 8;
+const ZMQ_CTX_IPV6: c_int = 42;
+const ZMQ_BLOCKY: c_int = 70;
+
 struct RawContext {
-    ctx: *mut c_void,
+    // Null once the C context has been destroyed. Destroying it holds
+    // the write lock for as long as `zmq_ctx_destroy` blocks, so that
+    // no other use of the pointer can overlap with it.
+    ctx: RwLock<*mut c_void>,
+    // The sockets created from this context that are still open,
+    // keyed by their raw pointer.
+    sockets: Mutex<HashMap<usize, SocketType>>,
+    sockets_closed: Condvar,
 }
 
 impl RawContext {
+    fn new(ctx: *mut c_void) -> RawContext {
+        RawContext {
+            ctx: RwLock::new(ctx),
+            sockets: Mutex::new(HashMap::new()),
+            sockets_closed: Condvar::new(),
+        }
+    }
+
+    /// Call `f` with the C context, failing with `Error::ETERM` if it
+    /// has been destroyed.
+    ///
+    /// This does not wait for a concurrent `destroy()`, which may
+    /// block until all sockets are closed, but fails with
+    /// `Error::ETERM` as well.
+    fn with_ctx<T, F>(&self, f: F) -> Result<T>
+        where F: FnOnce(*mut c_void) -> Result<T>
+    {
+        let ctx = match self.ctx.try_read() {
+            Ok(ctx) => ctx,
+            Err(_) => return Err(Error::ETERM),
+        };
+        if ctx.is_null() {
+            return Err(Error::ETERM);
+        }
+        f(*ctx)
+    }
+
     fn destroy(&self) -> Result<()> {
-        zmq_try!(unsafe { zmq_sys::zmq_ctx_destroy(self.ctx) });
+        let mut ctx = self.ctx.write().unwrap();
+        if ctx.is_null() {
+            return Ok(());
+        }
+        zmq_try!(unsafe { zmq_sys::zmq_ctx_destroy(*ctx) });
+        *ctx = ptr::null_mut();
+        Ok(())
+    }
+
+    fn shutdown(&self) -> Result<()> {
+        let result = self.with_ctx(|ctx| {
+            zmq_try!(unsafe { zmq_sys::zmq_ctx_shutdown(ctx) });
+            Ok(())
+        });
+        match result {
+            Err(Error::ETERM) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn get(&self, option: c_int) -> Result<i32> {
+        self.with_ctx(|ctx| {
+            let rc = zmq_try!(unsafe { zmq_sys::zmq_ctx_get(ctx, option) });
+            Ok(rc as i32)
+        })
+    }
+
+    fn set(&self, option: c_int, value: i32) -> Result<()> {
+        self.with_ctx(|ctx| {
+            zmq_try!(unsafe { zmq_sys::zmq_ctx_set(ctx, option, value as c_int) });
+            Ok(())
+        })
+    }
+
+    fn register_socket(&self, sock: *mut c_void, socket_type: SocketType) {
+        self.sockets.lock().unwrap().insert(sock as usize, socket_type);
+    }
+
+    fn unregister_socket(&self, sock: *mut c_void) {
+        let mut sockets = self.sockets.lock().unwrap();
+        sockets.remove(&(sock as usize));
+        if sockets.is_empty() {
+            self.sockets_closed.notify_all();
+        }
+    }
+
+    /// Wait until all sockets have been closed, returning the types
+    /// of the remaining sockets if `timeout` expires first.
+    fn wait_sockets_closed(&self, timeout: Duration) -> result::Result<(), Vec<SocketType>> {
+        let deadline = Instant::now() + timeout;
+        let mut sockets = self.sockets.lock().unwrap();
+        while !sockets.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(sockets.values().cloned().collect());
+            }
+            sockets = self.sockets_closed.wait_timeout(sockets, deadline - now).unwrap().0;
+        }
         Ok(())
     }
 }
@@ -380,28 +688,38 @@ impl Context {
     /// Create a new reference-counted context handle.
     pub fn new() -> Context {
         Context {
-            raw: Arc::new(RawContext {
-                ctx: unsafe { zmq_sys::zmq_ctx_new() }
-            })
+            raw: Arc::new(RawContext::new(unsafe { zmq_sys::zmq_ctx_new() }))
         }
     }
 
+    /// Create a new typed socket, e.g. `ctx.typed_socket::<Sub>()`.
+    ///
+    /// See `TypedSocket` for details.
+    pub fn typed_socket<K: SocketKind>(&self) -> Result<TypedSocket<K>> {
+        TypedSocket::new(self)
+    }
+
     /// Create a new socket.
     ///
     /// Note that the returned socket keeps a an `Arc` reference to
     /// the context it was created from, and will keep that context
     /// from being dropped while being live.
     pub fn socket(&self, socket_type: SocketType) -> Result<Socket> {
-        let sock = unsafe { zmq_sys::zmq_socket(self.raw.ctx, socket_type as c_int) };
-
-        if sock.is_null() {
-            return Err(errno_to_error());
-        }
+        let sock = try!(self.raw.with_ctx(|ctx| {
+            let sock = unsafe { zmq_sys::zmq_socket(ctx, socket_type as c_int) };
+            if sock.is_null() {
+                return Err(errno_to_error());
+            }
+            self.raw.register_socket(sock, socket_type);
+            Ok(sock)
+        }));
 
         Ok(Socket {
             sock: sock,
             context: Some(self.clone()),
             owned: true,
+            bound: Mutex::new(Vec::new()),
+            connected: Mutex::new(Vec::new()),
         })
     }
 
@@ -410,6 +728,149 @@ impl Context {
     pub fn destroy(&mut self) -> Result<()> {
         self.raw.destroy()
     }
+
+    /// Shut down the context, see `zmq_ctx_shutdown`(3).
+    ///
+    /// Any blocking operation currently in progress on sockets created
+    /// from this context will return with `Error::ETERM`, as will all
+    /// further operations except for closing the sockets. The context
+    /// itself is destroyed once the last reference to it is dropped.
+    pub fn shutdown(&self) -> Result<()> {
+        self.raw.shutdown()
+    }
+
+    /// Obtain a handle that can be used to shut down this context
+    /// from another thread.
+    ///
+    /// The handle does not keep the context alive.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle { raw: Arc::downgrade(&self.raw) }
+    }
+
+    /// Shut down and terminate the context, waiting at most `timeout`
+    /// for its sockets to be closed.
+    ///
+    /// The context is shut down first, so blocking operations on its
+    /// sockets return `Error::ETERM`, giving their owners the chance
+    /// to close them. If sockets remain open when `timeout` expires,
+    /// their types are returned in the inner `Err`, and the context is
+    /// left shut down but not terminated.
+    ///
+    /// After successful termination, creating new sockets from this
+    /// context (or any of its clones) fails with `Error::ETERM`.
+    pub fn term_with_timeout(&self, timeout: Duration)
+                             -> Result<result::Result<(), Vec<SocketType>>> {
+        try!(self.raw.shutdown());
+        if let Err(open) = self.raw.wait_sockets_closed(timeout) {
+            return Ok(Err(open));
+        }
+        let mut e = self.raw.destroy();
+        while e == Err(Error::EINTR) {
+            e = self.raw.destroy();
+        }
+        e.map(Ok)
+    }
+
+    /// Get the size of the 0MQ thread pool.
+    pub fn get_io_threads(&self) -> Result<i32> {
+        self.raw.get(ZMQ_IO_THREADS)
+    }
+
+    /// Set the size of the 0MQ thread pool.
+    ///
+    /// This only has an effect if called before the first socket is
+    /// created from the context.
+    pub fn set_io_threads(&self, value: i32) -> Result<()> {
+        self.raw.set(ZMQ_IO_THREADS, value)
+    }
+
+    /// Get the maximum number of sockets allowed on the context.
+    pub fn get_max_sockets(&self) -> Result<i32> {
+        self.raw.get(ZMQ_MAX_SOCKETS)
+    }
+
+    /// Set the maximum number of sockets allowed on the context.
+    pub fn set_max_sockets(&self, value: i32) -> Result<()> {
+        self.raw.set(ZMQ_MAX_SOCKETS, value)
+    }
+
+    /// Get the largest number of sockets that `set_max_sockets()`
+    /// will accept.
+    pub fn get_socket_limit(&self) -> Result<i32> {
+        self.raw.get(ZMQ_SOCKET_LIMIT)
+    }
+
+    /// Set the scheduling priority for the internal I/O threads.
+    ///
+    /// This option is write-only, and only has an effect if called
+    /// before the first socket is created from the context.
+    pub fn set_thread_priority(&self, value: i32) -> Result<()> {
+        self.raw.set(ZMQ_THREAD_PRIORITY, value)
+    }
+
+    /// Get the scheduling policy for the internal I/O threads.
+    pub fn get_thread_sched_policy(&self) -> Result<i32> {
+        self.raw.get(ZMQ_THREAD_SCHED_POLICY)
+    }
+
+    /// Set the scheduling policy for the internal I/O threads.
+    ///
+    /// The value is one of the OS-specific `SCHED_*` constants. This
+    /// only has an effect if called before the first socket is
+    /// created from the context.
+    pub fn set_thread_sched_policy(&self, value: i32) -> Result<()> {
+        self.raw.set(ZMQ_THREAD_SCHED_POLICY, value)
+    }
+
+    /// Add a CPU to the affinity list of the internal I/O threads.
+    ///
+    /// This only has an effect if called before the first socket is
+    /// created from the context.
+    pub fn add_thread_affinity_cpu(&self, cpu: i32) -> Result<()> {
+        self.raw.set(ZMQ_THREAD_AFFINITY_CPU_ADD, cpu)
+    }
+
+    /// Remove a CPU from the affinity list of the internal I/O threads.
+    pub fn remove_thread_affinity_cpu(&self, cpu: i32) -> Result<()> {
+        self.raw.set(ZMQ_THREAD_AFFINITY_CPU_REMOVE, cpu)
+    }
+
+    /// Return true if sockets created from the context will have IPv6
+    /// enabled by default.
+    pub fn is_ipv6(&self) -> Result<bool> {
+        self.raw.get(ZMQ_CTX_IPV6).map(|v| v == 1)
+    }
+
+    /// Set the default value of the `ZMQ_IPV6` option for sockets
+    /// created from the context.
+    pub fn set_ipv6(&self, value: bool) -> Result<()> {
+        self.raw.set(ZMQ_CTX_IPV6, if value { 1 } else { 0 })
+    }
+
+    /// Return true if context termination blocks until all pending
+    /// messages have been sent.
+    pub fn is_blocky(&self) -> Result<bool> {
+        self.raw.get(ZMQ_BLOCKY).map(|v| v == 1)
+    }
+
+    /// Set whether context termination blocks until all pending
+    /// messages have been sent.
+    ///
+    /// When disabled, sockets created from the context default to a
+    /// linger period of zero.
+    pub fn set_blocky(&self, value: bool) -> Result<()> {
+        self.raw.set(ZMQ_BLOCKY, if value { 1 } else { 0 })
+    }
+
+    /// Get the maximum message size allowed by the context.
+    pub fn get_max_msgsz(&self) -> Result<i32> {
+        self.raw.get(ZMQ_MAX_MSGSZ)
+    }
+
+    /// Set the maximum message size allowed by the context.
+    pub fn set_max_msgsz(&self, value: i32) -> Result<()> {
+        self.raw.set(ZMQ_MAX_MSGSZ, value)
+    }
 }
 
 impl Default for Context {
@@ -418,6 +879,28 @@ impl Default for Context {
     }
 }
 
+/// A handle for shutting down a `Context` from any thread.
+///
+/// This is obtained via `Context::shutdown_handle()`, and can be
+/// cloned freely. It is mainly useful to unblock threads stuck in
+/// `recv()` or `proxy()` without having to destroy the context.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    raw: Weak<RawContext>,
+}
+
+impl ShutdownHandle {
+    /// Shut down the context, see `Context::shutdown()`.
+    ///
+    /// Does nothing if the context has already been dropped.
+    pub fn shutdown(&self) -> Result<()> {
+        match self.raw.upgrade() {
+            Some(raw) => raw.shutdown(),
+            None => Ok(()),
+        }
+    }
+}
+
 /// A socket, the central object in 0MQ.
 pub struct Socket {
     sock: *mut c_void,
@@ -426,18 +909,22 @@ pub struct Socket {
     #[allow(dead_code)]
     context: Option<Context>,
     owned: bool,
+    // Endpoints this socket is currently bound or connected to, in
+    // the order they were added. Bound endpoints are kept both as
+    // passed to `bind()` and in their resolved form.
+    bound: Mutex<Vec<(String, String)>>,
+    connected: Mutex<Vec<String>>,
 }
 
 unsafe impl Send for Socket {}
 
 impl Drop for Socket {
     fn drop(&mut self) {
-        if self.owned {
-            if unsafe { zmq_sys::zmq_close(self.sock) } == -1 {
-                panic!(errno_to_error());
-            } else {
-                debug!("socket dropped");
-            }
+        // Panicking here could abort the process if we are already
+        // unwinding, so the error is only logged; use `close()` to
+        // observe it.
+        if let Err(e) = self.close_owned() {
+            error!("failed to close socket: {}", e);
         }
     }
 }
@@ -512,6 +999,40 @@ macro_rules! sockopts {
     };
 }
 
+macro_rules! sockopt_durations {
+    () => ();
+    ( $(#[$meta:meta])* ($getter:ident, $setter:ident) => ($raw_getter:ident, $raw_setter:ident)
+      in $unit_ms:expr, none = $none:expr;
+      $($rest:tt)*
+    ) => {
+        $(#[$meta])*
+        pub fn $getter(&self) -> Result<Option<Duration>> {
+            self.$raw_getter().map(|raw| raw_to_duration(raw as i64, $none, $unit_ms))
+        }
+        $(#[$meta])*
+        pub fn $setter(&self, value: Option<Duration>) -> Result<()> {
+            let raw = try!(optional_duration_to_raw(value, $none, $unit_ms, i32::max_value() as i64));
+            self.$raw_setter(raw as i32)
+        }
+        sockopt_durations!($($rest)*);
+    };
+    ( $(#[$meta:meta])* ($getter:ident, $setter:ident) => ($raw_getter:ident, $raw_setter:ident)
+      in $unit_ms:expr;
+      $($rest:tt)*
+    ) => {
+        $(#[$meta])*
+        pub fn $getter(&self) -> Result<Duration> {
+            self.$raw_getter().and_then(|raw| raw_to_required_duration(raw as i64, $unit_ms))
+        }
+        $(#[$meta])*
+        pub fn $setter(&self, value: Duration) -> Result<()> {
+            let raw = try!(duration_to_raw(value, $unit_ms, i32::max_value() as i64));
+            self.$raw_setter(raw as i32)
+        }
+        sockopt_durations!($($rest)*);
+    };
+}
+
 /// Sendable over a `Socket`.
 ///
 /// A type can implement this trait there is an especially efficient
@@ -553,7 +1074,48 @@ impl Socket {
             sock: sock,
             context: None,
             owned: true,
+            bound: Mutex::new(Vec::new()),
+            connected: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Close the socket, reporting failure instead of only logging it
+    /// as dropping the socket does.
+    ///
+    /// Pending outbound messages are still sent in the background,
+    /// within the linger period set via `set_linger()`. Whether or not
+    /// closing succeeds, the socket's reference on its context is
+    /// released, so `Context::term_with_timeout()` no longer waits for
+    /// it.
+    pub fn close(mut self) -> Result<()> {
+        self.close_owned()
+    }
+
+    /// Set the linger period, then close the socket; see `close()`.
+    ///
+    /// Pending outbound messages are sent for at most `linger`, after
+    /// which they are discarded when the context is terminated.
+    pub fn close_with_linger(self, linger: Duration) -> Result<()> {
+        try!(self.set_linger_duration(Some(linger)));
+        self.close()
+    }
+
+    fn close_owned(&mut self) -> Result<()> {
+        let result = if self.owned {
+            self.owned = false;
+            if unsafe { zmq_sys::zmq_close(self.sock) } == -1 {
+                Err(errno_to_error())
+            } else {
+                debug!("socket closed");
+                Ok(())
+            }
+        } else {
+            Ok(())
+        };
+        if let Some(context) = self.context.take() {
+            context.raw.unregister_socket(self.sock);
         }
+        result
     }
 
     /// Return the inner pointer to this Socket.
@@ -566,23 +1128,149 @@ impl Socket {
     }
 
     /// Accept connections on a socket.
-    pub fn bind(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
-        zmq_try!(unsafe { zmq_sys::zmq_bind(self.sock, c_str.as_ptr()) });
+    ///
+    /// On success, the endpoint is added to the list returned by
+    /// `bound_endpoints()`. Wildcard addresses, such as
+    /// `"tcp://127.0.0.1:*"`, are recorded in their resolved form, as
+    /// returned by `get_last_endpoint()`.
+    ///
+    /// The endpoint may be given as a string or as an `Endpoint`; an
+    /// endpoint containing a NUL byte yields `Err(Error::EINVAL)`.
+    pub fn bind<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        let endpoint = endpoint.as_endpoint();
+        try!(self.endpoint_op("bind", &endpoint, zmq_sys::zmq_bind));
+        let resolved = match self.last_endpoint_string() {
+            Ok(Ok(resolved)) => resolved,
+            _ => endpoint.to_string(),
+        };
+        self.bound.lock().unwrap().push((endpoint.into_owned(), resolved));
+        Ok(())
+    }
+
+    /// Stop accepting connections on a previously bound endpoint.
+    ///
+    /// When the socket was bound to a wildcard address, the resolved
+    /// endpoint (see `get_last_endpoint()`) must be passed. Otherwise,
+    /// the endpoint may also be given as passed to `bind()`, or, for
+    /// TCP, using any host name resolving to the bound address.
+    pub fn unbind<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        let endpoint = endpoint.as_endpoint();
+        try!(self.endpoint_op("unbind", &endpoint, zmq_sys::zmq_unbind));
+        remove_bound_endpoint(&self.bound, &endpoint);
         Ok(())
     }
 
     /// Connect a socket.
-    pub fn connect(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
-        zmq_try!(unsafe { zmq_sys::zmq_connect(self.sock, c_str.as_ptr()) });
+    ///
+    /// On success, the endpoint is added to the list returned by
+    /// `connected_endpoints()`.
+    pub fn connect<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        let endpoint = endpoint.as_endpoint();
+        try!(self.endpoint_op("connect", &endpoint, zmq_sys::zmq_connect));
+        self.connected.lock().unwrap().push(endpoint.into_owned());
         Ok(())
     }
 
     /// Disconnect a previously connected socket
-    pub fn disconnect(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
-        zmq_try!(unsafe { zmq_sys::zmq_disconnect(self.sock, c_str.as_ptr()) });
+    pub fn disconnect<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        let endpoint = endpoint.as_endpoint();
+        try!(self.endpoint_op("disconnect", &endpoint, zmq_sys::zmq_disconnect));
+        remove_endpoint(&self.connected, &endpoint);
+        Ok(())
+    }
+
+    // Apply `zmq_bind()` or one of its siblings to `endpoint`, attaching
+    // the operation and endpoint to a failure.
+    fn endpoint_op(&self,
+                   operation: &'static str,
+                   endpoint: &str,
+                   f: unsafe extern "C" fn(*mut c_void, *const libc::c_char) -> c_int)
+                   -> result::Result<(), ContextError> {
+        let rc = endpoint_cstring(endpoint).and_then(|c_str| {
+            zmq_try!(unsafe { f(self.sock, c_str.as_ptr()) });
+            Ok(())
+        });
+        rc.map_err(|e| e.with_context(operation, Some(endpoint)))
+    }
+
+    /// Join a group, to receive the messages published to it on a
+    /// `DISH` socket.
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    pub fn join(&self, group: &str) -> Result<()> {
+        let c_str = try!(ffi::CString::new(group).map_err(|_| Error::EINVAL));
+        zmq_try!(unsafe { zmq_sys::zmq_join(self.sock, c_str.as_ptr()) });
+        Ok(())
+    }
+
+    /// Leave a group previously joined with `join()`.
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    pub fn leave(&self, group: &str) -> Result<()> {
+        let c_str = try!(ffi::CString::new(group).map_err(|_| Error::EINVAL));
+        zmq_try!(unsafe { zmq_sys::zmq_leave(self.sock, c_str.as_ptr()) });
+        Ok(())
+    }
+
+    /// Return the endpoints this socket is currently bound to.
+    ///
+    /// Only endpoints bound via `bind()` are tracked; this does not
+    /// include endpoints bound before the socket was wrapped using
+    /// `from_raw()`.
+    pub fn bound_endpoints(&self) -> Vec<String> {
+        self.bound.lock().unwrap().iter().map(|&(_, ref resolved)| resolved.clone()).collect()
+    }
+
+    /// Return the endpoints this socket is currently connected to.
+    ///
+    /// Only endpoints connected via `connect()` are tracked; this does
+    /// not include endpoints connected before the socket was wrapped
+    /// using `from_raw()`.
+    pub fn connected_endpoints(&self) -> Vec<String> {
+        self.connected.lock().unwrap().clone()
+    }
+
+    /// Unbind all endpoints listed by `bound_endpoints()`.
+    ///
+    /// Stops at the first failure, leaving the remaining endpoints
+    /// bound.
+    pub fn unbind_all(&self) -> result::Result<(), ContextError> {
+        for endpoint in self.bound_endpoints() {
+            try!(self.unbind(&endpoint));
+        }
+        Ok(())
+    }
+
+    /// Disconnect all endpoints listed by `connected_endpoints()`.
+    ///
+    /// Stops at the first failure, leaving the remaining endpoints
+    /// connected.
+    pub fn disconnect_all(&self) -> result::Result<(), ContextError> {
+        for endpoint in self.connected_endpoints() {
+            try!(self.disconnect(&endpoint));
+        }
+        Ok(())
+    }
+
+    /// Start monitoring socket events.
+    ///
+    /// The selected `events` are published on a `PAIR` socket bound
+    /// to `endpoint`, which must use the `inproc` transport. Use
+    /// `Monitor::connect()` to receive them.
+    ///
+    /// Like for `bind()`, the endpoint may be given as a string or as
+    /// an `Endpoint`; an endpoint containing a NUL byte yields
+    /// `Err(Error::EINVAL)`.
+    pub fn monitor<E: ?Sized + AsEndpoint>(&self, endpoint: &E, events: MonitorEvents)
+                                           -> Result<()> {
+        let c_str = try!(endpoint_cstring(&endpoint.as_endpoint()));
+        zmq_try!(unsafe {
+            zmq_sys::zmq_socket_monitor(self.sock, c_str.as_ptr(), events.bits() as c_int)
+        });
+        Ok(())
+    }
+
+    /// Stop monitoring socket events, as started with `monitor()`.
+    pub fn stop_monitor(&self) -> Result<()> {
+        zmq_try!(unsafe { zmq_sys::zmq_socket_monitor(self.sock, ptr::null(), 0) });
         Ok(())
     }
 
@@ -596,6 +1284,18 @@ impl Socket {
         data.send(self, flags)
     }
 
+    /// Send static data without copying it, using `zmq_send_const`.
+    ///
+    /// This is useful for constant payloads, such as protocol headers
+    /// or heartbeats, that are sent frequently.
+    pub fn send_static(&self, data: &'static [u8], flags: i32) -> Result<()> {
+        zmq_try!(unsafe {
+            zmq_sys::zmq_send_const(self.sock, data.as_ptr() as *const c_void,
+                                    data.len(), flags as c_int)
+        });
+        Ok(())
+    }
+
     /// Send a `Message` message.
     #[deprecated(since="0.9.0", note="Use `send` instead")]
     pub fn send_msg(&self, msg: Message, flags: i32) -> Result<()> {
@@ -626,6 +1326,33 @@ impl Socket {
         }
     }
 
+    /// Send a multipart message, with one part per buffer in `parts`,
+    /// using a single call to `zmq_sendiov`.
+    ///
+    /// This works with any slice of byte buffers, such as
+    /// `std::io::IoSlice`, `&[u8]` or `Vec<u8>`. The data is copied
+    /// into the message parts by libzmq. An empty `parts` slice is
+    /// rejected with `Error::EINVAL`.
+    ///
+    /// `SNDMORE` is added to `flags` when calling `zmq_sendiov`, since
+    /// libzmq applies the flags to each part as given and only clears
+    /// `SNDMORE` on the last one. Unlike with `send_multipart()`, the
+    /// last part thus always completes the message.
+    pub fn send_vectored<T>(&self, parts: &[T], flags: i32) -> Result<()>
+        where T: Deref<Target=[u8]>
+    {
+        let mut iov: Vec<zmq_sys::iovec> = parts.iter().map(|part| {
+            zmq_sys::iovec {
+                iov_base: part.as_ptr() as *mut c_void,
+                iov_len: part.len(),
+            }
+        }).collect();
+        zmq_try!(unsafe {
+            zmq_sys::zmq_sendiov(self.sock, iov.as_mut_ptr(), iov.len(), (flags | SNDMORE) as c_int)
+        });
+        Ok(())
+    }
+
     /// Receive a message into a `Message`. The length passed to zmq_msg_recv
     /// is the length of the buffer.
     pub fn recv(&self, msg: &mut Message, flags: i32) -> Result<()> {
@@ -680,6 +1407,52 @@ impl Socket {
         Ok(parts)
     }
 
+    /// Receive a multipart message into the given buffers, one part
+    /// per buffer, without allocating.
+    ///
+    /// This works with any slice of mutable byte buffers, such as
+    /// `std::io::IoSliceMut` or `&mut [u8]`. The whole message is
+    /// always consumed, and truncation is reported in the returned
+    /// `RecvVectored`: parts larger than their buffer are truncated,
+    /// and parts beyond the given buffers are discarded. An empty
+    /// `parts` slice is rejected with `Error::EINVAL`.
+    ///
+    /// Once the first part has been received, the remaining ones are
+    /// already available, and receiving them is retried if interrupted
+    /// (`EINTR`), so the socket is not left in the middle of a message.
+    ///
+    /// Note that this does not use `zmq_recviov`, as that allocates
+    /// a fresh buffer for each part instead of filling the given ones.
+    pub fn recv_vectored<T>(&self, parts: &mut [T], flags: i32) -> Result<RecvVectored>
+        where T: DerefMut<Target=[u8]>
+    {
+        if parts.is_empty() {
+            return Err(Error::EINVAL);
+        }
+        let mut result = RecvVectored {
+            sizes: Vec::with_capacity(parts.len()),
+            truncated: false,
+        };
+        loop {
+            let first = result.sizes.is_empty();
+            let buf: &mut [u8] = match parts.get_mut(result.sizes.len()) {
+                Some(part) => part,
+                None => &mut [],
+            };
+            let size = loop {
+                match self.recv_into(buf, flags) {
+                    Err(Error::EINTR) if !first => {}
+                    size => break try!(size),
+                }
+            };
+            result.truncated |= size > buf.len();
+            result.sizes.push(size);
+            if !try!(self.get_rcvmore()) {
+                return Ok(result);
+            }
+        }
+    }
+
     sockopts! {
         /// Accessor for the `ZMQ_IPV6` option.
         (is_ipv6, set_ipv6) => ZMQ_IPV6 as bool,
@@ -691,6 +1464,26 @@ impl Socket {
         (is_conflate, set_conflate) => ZMQ_CONFLATE as bool,
         (is_probe_router, set_probe_router) => ZMQ_PROBE_ROUTER as bool,
         (is_router_mandatory, set_router_mandatory) => ZMQ_ROUTER_MANDATORY as bool,
+        (_, set_xpub_verbose) => ZMQ_XPUB_VERBOSE as bool,
+        (_, set_router_raw) => ZMQ_ROUTER_RAW as bool,
+        (_, set_req_correlate) => ZMQ_REQ_CORRELATE as bool,
+        (_, set_req_relaxed) => ZMQ_REQ_RELAXED as bool,
+        if ZMQ_VERSION_4_1 {
+            (_, set_router_handover) => ZMQ_ROUTER_HANDOVER as bool,
+        },
+        if ZMQ_VERSION_4_2 {
+            (_, set_xpub_manual) => ZMQ_XPUB_MANUAL as bool,
+            (_, set_stream_notify) => ZMQ_STREAM_NOTIFY as bool,
+            (is_invert_matching, set_invert_matching) => ZMQ_INVERT_MATCHING as bool,
+        },
+        if ZMQ_VERSION_4_3 {
+            if ZMQ_HAS_DRAFT {
+                (is_zap_enforce_domain, set_zap_enforce_domain) => ZMQ_ZAP_ENFORCE_DOMAIN as bool,
+                /// Accessor for the `ZMQ_LOOPBACK_FASTPATH` option, which
+                /// only has an effect on Windows.
+                (is_loopback_fastpath, set_loopback_fastpath) => ZMQ_LOOPBACK_FASTPATH as bool,
+            },
+        },
         if ZMQ_HAS_CURVE {
             (is_curve_server, set_curve_server) => ZMQ_CURVE_SERVER as bool,
         },
@@ -701,9 +1494,12 @@ impl Socket {
     }
 
     /// Return the type of this socket.
+    ///
+    /// Socket types unknown to this crate, such as draft types without
+    /// the `draft` feature, yield `Err(Error::EINVAL)`.
     pub fn get_socket_type(&self) -> Result<SocketType> {
-        sockopt::get(self.sock, Constants::ZMQ_TYPE.to_raw()).map(|ty| {
-            match ty {
+        sockopt::get(self.sock, Constants::ZMQ_TYPE.to_raw()).and_then(|ty| {
+            Ok(match ty {
                 0 => SocketType::PAIR,
                 1 => SocketType::PUB,
                 2 => SocketType::SUB,
@@ -716,8 +1512,26 @@ impl Socket {
                 9 => SocketType::XPUB,
                 10 => SocketType::XSUB,
                 11 => SocketType::STREAM,
-                _ => panic!("socket type is out of range!")
-            }
+                #[cfg(feature = "draft")]
+                12 => SocketType::SERVER,
+                #[cfg(feature = "draft")]
+                13 => SocketType::CLIENT,
+                #[cfg(feature = "draft")]
+                14 => SocketType::RADIO,
+                #[cfg(feature = "draft")]
+                15 => SocketType::DISH,
+                #[cfg(feature = "draft")]
+                16 => SocketType::GATHER,
+                #[cfg(feature = "draft")]
+                17 => SocketType::SCATTER,
+                #[cfg(feature = "draft")]
+                18 => SocketType::DGRAM,
+                #[cfg(feature = "draft")]
+                19 => SocketType::PEER,
+                #[cfg(feature = "draft")]
+                20 => SocketType::CHANNEL,
+                _ => return Err(Error::EINVAL),
+            })
         })
     }
 
@@ -794,6 +1608,28 @@ impl Socket {
         (_, set_identity) => ZMQ_IDENTITY as &[u8],
         (_, set_subscribe) => ZMQ_SUBSCRIBE as &[u8],
         (_, set_unsubscribe) => ZMQ_UNSUBSCRIBE as &[u8],
+        if ZMQ_VERSION_4_1 {
+            /// Set the identity to assign to the next connected peer;
+            /// only valid for `ROUTER` and `STREAM` sockets.
+            (_, set_connect_rid) => ZMQ_CONNECT_RID as &[u8],
+        },
+        if ZMQ_VERSION_4_2 {
+            (_, set_xpub_welcome_msg) => ZMQ_XPUB_WELCOME_MSG as &[u8],
+            (get_heartbeat_ivl, set_heartbeat_ivl) => ZMQ_HEARTBEAT_IVL as i32,
+            (get_heartbeat_ttl, set_heartbeat_ttl) => ZMQ_HEARTBEAT_TTL as i32,
+            (get_heartbeat_timeout, set_heartbeat_timeout) => ZMQ_HEARTBEAT_TIMEOUT as i32,
+            (get_connect_timeout, set_connect_timeout) => ZMQ_CONNECT_TIMEOUT as i32,
+            (get_tcp_maxrt, set_tcp_maxrt) => ZMQ_TCP_MAXRT as i32,
+            (get_multicast_maxtpdu, set_multicast_maxtpdu) => ZMQ_MULTICAST_MAXTPDU as i32,
+        },
+        if ZMQ_VERSION_4_3 {
+            if ZMQ_HAS_DRAFT {
+                /// Accessor for the `ZMQ_ROUTER_NOTIFY` option, a
+                /// combination of `ZMQ_NOTIFY_CONNECT` (1) and
+                /// `ZMQ_NOTIFY_DISCONNECT` (2).
+                (get_router_notify, set_router_notify) => ZMQ_ROUTER_NOTIFY as i32,
+            },
+        },
     }
 
     pub fn get_identity(&self) -> Result<Vec<u8>> {
@@ -807,15 +1643,18 @@ impl Socket {
         sockopt::get_string(self.sock, Constants::ZMQ_SOCKS_PROXY.to_raw(), 255, true)
     }
 
+    /// Return the security mechanism used by this socket.
+    ///
+    /// Mechanisms unknown to this crate yield `Err(Error::EINVAL)`.
     pub fn get_mechanism(&self) -> Result<Mechanism> {
-        sockopt::get(self.sock, Constants::ZMQ_MECHANISM.to_raw()).map(|mech| {
-            match mech {
+        sockopt::get(self.sock, Constants::ZMQ_MECHANISM.to_raw()).and_then(|mech| {
+            Ok(match mech {
                 0 => Mechanism::ZMQ_NULL,
                 1 => Mechanism::ZMQ_PLAIN,
                 2 => Mechanism::ZMQ_CURVE,
                 3 => Mechanism::ZMQ_GSSAPI,
-                _ => panic!("Mechanism is out of range!")
-            }
+                _ => return Err(Error::EINVAL),
+            })
         })
     }
 
@@ -842,7 +1681,17 @@ impl Socket {
     /// used with the wildcard address (`"*"`), in the address
     /// returned, the wildcard will be expanded into the any address
     /// (i.e. `0.0.0.0` with IPv4).
-    pub fn get_last_endpoint(&self) -> Result<result::Result<String, Vec<u8>>> {
+    ///
+    /// If the address is not valid UTF-8 or cannot be parsed as an
+    /// `Endpoint`, its raw bytes are returned in the inner `Err`.
+    pub fn get_last_endpoint(&self) -> Result<result::Result<Endpoint, Vec<u8>>> {
+        Ok(try!(self.last_endpoint_string()).and_then(|s| match s.parse() {
+            Ok(endpoint) => Ok(endpoint),
+            Err(_) => Err(s.into_bytes()),
+        }))
+    }
+
+    fn last_endpoint_string(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 256 + 9 + 1 = maximum inproc name size (= 256) + "inproc://".len() (= 9), plus null byte
         sockopt::get_string(self.sock, Constants::ZMQ_LAST_ENDPOINT.to_raw(), 256 + 9 + 1, true)
     }
@@ -890,11 +1739,89 @@ impl Socket {
         sockopt::get_string(self.sock, Constants::ZMQ_GSSAPI_SERVICE_PRINCIPAL.to_raw(), 260, true)
     }
 
+    sockopt_durations! {
+        /// Accessor for `ZMQ_RCVTIMEO` as a `Duration`; `None` means
+        /// to block indefinitely.
+        (get_rcvtimeo_duration, set_rcvtimeo_duration) => (get_rcvtimeo, set_rcvtimeo)
+            in 1, none = -1;
+        /// Accessor for `ZMQ_SNDTIMEO` as a `Duration`; `None` means
+        /// to block indefinitely.
+        (get_sndtimeo_duration, set_sndtimeo_duration) => (get_sndtimeo, set_sndtimeo)
+            in 1, none = -1;
+        /// Accessor for `ZMQ_LINGER` as a `Duration`; `None` means to
+        /// linger indefinitely.
+        (get_linger_duration, set_linger_duration) => (get_linger, set_linger)
+            in 1, none = -1;
+        /// Accessor for `ZMQ_RECONNECT_IVL` as a `Duration`; `None`
+        /// disables reconnection.
+        (get_reconnect_ivl_duration, set_reconnect_ivl_duration)
+            => (get_reconnect_ivl, set_reconnect_ivl) in 1, none = -1;
+        /// Accessor for `ZMQ_RECONNECT_IVL_MAX` as a `Duration`;
+        /// `None` disables exponential backoff.
+        (get_reconnect_ivl_max_duration, set_reconnect_ivl_max_duration)
+            => (get_reconnect_ivl_max, set_reconnect_ivl_max) in 1, none = 0;
+        /// Accessor for `ZMQ_RECOVERY_IVL` as a `Duration`. A negative
+        /// raw value is reported as `Err(Error::EINVAL)`.
+        (get_recovery_ivl_duration, set_recovery_ivl_duration)
+            => (get_recovery_ivl, set_recovery_ivl) in 1;
+        /// Accessor for `ZMQ_HANDSHAKE_IVL` as a `Duration`; `None`
+        /// disables the handshake timeout.
+        (get_handshake_ivl_duration, set_handshake_ivl_duration)
+            => (get_handshake_ivl, set_handshake_ivl) in 1, none = 0;
+        /// Accessor for `ZMQ_TCP_KEEPALIVE_IDLE` as a `Duration`, with
+        /// a resolution of seconds; `None` uses the OS default.
+        (get_tcp_keepalive_idle_duration, set_tcp_keepalive_idle_duration)
+            => (get_tcp_keepalive_idle, set_tcp_keepalive_idle) in 1000, none = -1;
+        /// Accessor for `ZMQ_TCP_KEEPALIVE_INTVL` as a `Duration`, with
+        /// a resolution of seconds; `None` uses the OS default.
+        (get_tcp_keepalive_intvl_duration, set_tcp_keepalive_intvl_duration)
+            => (get_tcp_keepalive_intvl, set_tcp_keepalive_intvl) in 1000, none = -1;
+        /// Accessor for `ZMQ_HEARTBEAT_IVL` as a `Duration`; `None`
+        /// disables heartbeats.
+        #[cfg(ZMQ_VERSION_4_2 = "1")]
+        (get_heartbeat_ivl_duration, set_heartbeat_ivl_duration)
+            => (get_heartbeat_ivl, set_heartbeat_ivl) in 1, none = 0;
+        /// Accessor for `ZMQ_HEARTBEAT_TTL` as a `Duration`; `None`
+        /// means no time-to-live is announced to peers.
+        #[cfg(ZMQ_VERSION_4_2 = "1")]
+        (get_heartbeat_ttl_duration, set_heartbeat_ttl_duration)
+            => (get_heartbeat_ttl, set_heartbeat_ttl) in 1, none = 0;
+        /// Accessor for `ZMQ_HEARTBEAT_TIMEOUT` as a `Duration`; `None`
+        /// uses the heartbeat interval.
+        #[cfg(ZMQ_VERSION_4_2 = "1")]
+        (get_heartbeat_timeout_duration, set_heartbeat_timeout_duration)
+            => (get_heartbeat_timeout, set_heartbeat_timeout) in 1, none = 0;
+        /// Accessor for `ZMQ_CONNECT_TIMEOUT` as a `Duration`; `None`
+        /// disables the connect timeout.
+        #[cfg(ZMQ_VERSION_4_2 = "1")]
+        (get_connect_timeout_duration, set_connect_timeout_duration)
+            => (get_connect_timeout, set_connect_timeout) in 1, none = 0;
+        /// Accessor for `ZMQ_TCP_MAXRT` as a `Duration`; `None` uses
+        /// the OS default.
+        #[cfg(ZMQ_VERSION_4_2 = "1")]
+        (get_tcp_maxrt_duration, set_tcp_maxrt_duration)
+            => (get_tcp_maxrt, set_tcp_maxrt) in 1, none = 0;
+    }
+
+    #[cfg(ZMQ_VERSION_4_3 = "1")]
+    pub fn get_bindtodevice(&self) -> Result<result::Result<String, Vec<u8>>> {
+        // 16 = IFNAMSIZ, including the null byte
+        sockopt::get_string(self.sock, Constants::ZMQ_BINDTODEVICE.to_raw(), 16, true)
+    }
+
     sockopts! {
         (_, set_socks_proxy) => ZMQ_SOCKS_PROXY as Option<&str>,
         (_, set_plain_username) => ZMQ_PLAIN_USERNAME as Option<&str>,
         (_, set_plain_password) => ZMQ_PLAIN_PASSWORD as Option<&str>,
         (_, set_zap_domain) => ZMQ_ZAP_DOMAIN as &str,
+        if ZMQ_VERSION_4_3 {
+            (_, set_bindtodevice) => ZMQ_BINDTODEVICE as &str,
+            if ZMQ_HAS_DRAFT {
+                /// Add application metadata, given as `"X-Name:value"`,
+                /// to be sent to peers during the handshake.
+                (_, set_metadata) => ZMQ_METADATA as &str,
+            },
+        },
 
         if ZMQ_HAS_CURVE {
             (_, set_curve_publickey) => ZMQ_CURVE_PUBLICKEY as &[u8],
@@ -925,6 +1852,139 @@ impl Socket {
     pub fn poll(&self, events: PollEvents, timeout_ms: i64) -> Result<i32> {
         poll(&mut [self.as_poll_item(events)], timeout_ms)
     }
+
+    /// Do a call to `zmq_poll` with only this socket, with a `Duration`
+    /// timeout; see `poll_duration()`.
+    pub fn poll_duration(&self, events: PollEvents, timeout: Option<Duration>) -> Result<i32> {
+        poll_duration(&mut [self.as_poll_item(events)], timeout)
+    }
+
+    /// Handle pending events until the socket is no longer ready for
+    /// any of `interest`, for use with the descriptor returned by
+    /// `get_fd()` in a foreign event loop.
+    ///
+    /// That descriptor is edge-triggered: it becomes readable when
+    /// `ZMQ_EVENTS` may have changed, and any operation on the socket
+    /// can consume the notification. Hence, after the descriptor
+    /// signals readability, `handler` is called with the ready subset
+    /// of `interest` as long as `get_events()` reports any.
+    ///
+    /// The handler must consume the readiness it is given, i.e.
+    /// perform a non-blocking (`DONTWAIT`) send or receive each time,
+    /// or return `Error::EAGAIN` if it has nothing to do, e.g. nothing
+    /// left to send; otherwise, this loops forever. `Error::EAGAIN`
+    /// ends the loop successfully, while other errors are returned
+    /// right away.
+    ///
+    /// Once this returns `Ok(())` because the socket is no longer
+    /// ready, it is safe to wait for the descriptor again. After the
+    /// handler returned `Error::EAGAIN`, though, the socket may still
+    /// be ready, and the descriptor will not signal that again. As
+    /// sockets are writable most of the time, this matters for
+    /// `POLLOUT` in particular: call `drain_ready()` again as soon as
+    /// there is new data to send.
+    ///
+    /// ```no_run
+    /// # let ctx = zmq::Context::new();
+    /// # let socket = ctx.socket(zmq::PULL).unwrap();
+    /// // ... after the descriptor from `socket.get_fd()` became readable:
+    /// socket.drain_ready(zmq::POLLIN, |_| {
+    ///     let msg = try!(socket.recv_msg(zmq::DONTWAIT));
+    ///     println!("received {:?}", msg);
+    ///     Ok(())
+    /// }).unwrap();
+    /// ```
+    pub fn drain_ready<F>(&self, interest: PollEvents, mut handler: F) -> Result<()>
+        where F: FnMut(PollEvents) -> Result<()>
+    {
+        loop {
+            let ready = try!(self.get_events()) & interest;
+            if ready.is_empty() {
+                return Ok(());
+            }
+            match handler(ready) {
+                Ok(()) => {}
+                Err(Error::EAGAIN) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Outcome of `Socket::recv_vectored()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecvVectored {
+    /// The size of each part of the message. A size larger than the
+    /// length of the corresponding buffer indicates truncation; parts
+    /// beyond the given buffers are listed as well.
+    pub sizes: Vec<usize>,
+    /// True if any part was truncated, or the message had more parts
+    /// than buffers were given.
+    pub truncated: bool,
+}
+
+impl RecvVectored {
+    /// Return the number of parts of the message.
+    pub fn parts(&self) -> usize {
+        self.sizes.len()
+    }
+}
+
+fn endpoint_cstring(endpoint: &str) -> Result<ffi::CString> {
+    ffi::CString::new(endpoint).map_err(|_| Error::EINVAL)
+}
+
+// Remove the entry for the endpoint that `zmq_unbind` just unbound.
+fn remove_bound_endpoint(bound: &Mutex<Vec<(String, String)>>, endpoint: &str) {
+    if remove_bound_entry(bound, |requested, resolved| {
+        requested == endpoint || resolved == endpoint
+    }) {
+        return;
+    }
+    // libzmq resolves TCP addresses to look up the endpoint, so compare
+    // the resolved addresses as well. This may involve a DNS lookup,
+    // which must not happen while holding the lock.
+    let addrs = tcp_socket_addrs(endpoint);
+    if addrs.is_empty() {
+        return;
+    }
+    let candidates: Vec<String> = bound.lock().unwrap().iter()
+        .map(|&(_, ref resolved)| resolved.clone())
+        .collect();
+    let found = candidates.into_iter().find(|resolved| {
+        tcp_socket_addrs(resolved).iter().any(|addr| addrs.contains(addr))
+    });
+    if let Some(found) = found {
+        remove_bound_entry(bound, |_, resolved| resolved == found);
+    }
+}
+
+// Remove the first entry matching `pred`, returning whether there was one.
+fn remove_bound_entry<F>(bound: &Mutex<Vec<(String, String)>>, pred: F) -> bool
+    where F: Fn(&str, &str) -> bool
+{
+    let mut bound = bound.lock().unwrap();
+    match bound.iter().position(|&(ref requested, ref resolved)| pred(requested, resolved)) {
+        Some(pos) => {
+            bound.remove(pos);
+            true
+        }
+        None => false,
+    }
+}
+
+fn tcp_socket_addrs(endpoint: &str) -> Vec<SocketAddr> {
+    if !endpoint.starts_with("tcp://") {
+        return Vec::new();
+    }
+    endpoint["tcp://".len()..].to_socket_addrs().map(|addrs| addrs.collect()).unwrap_or_default()
+}
+
+fn remove_endpoint(endpoints: &Mutex<Vec<String>>, endpoint: &str) {
+    let mut endpoints = endpoints.lock().unwrap();
+    if let Some(pos) = endpoints.iter().position(|e| e == endpoint) {
+        endpoints.remove(pos);
+    }
 }
 
 bitflags! {
@@ -997,6 +2057,54 @@ impl<'a> PollItem<'a> {
     }
 }
 
+// Convert a duration into a 0MQ time value, counted in units of
+// `unit_ms` milliseconds. The value is rounded up, so that a short,
+// non-zero duration does not turn into zero.
+fn duration_to_raw(value: Duration, unit_ms: u64, max: i64) -> Result<i64> {
+    let unit_nanos = unit_ms * 1_000_000;
+    let nanos = value.as_secs().checked_mul(1_000_000_000)
+        .and_then(|n| n.checked_add(value.subsec_nanos() as u64));
+    match nanos {
+        Some(nanos) => {
+            let raw = nanos / unit_nanos + if nanos % unit_nanos != 0 { 1 } else { 0 };
+            if raw > max as u64 {
+                Err(Error::EINVAL)
+            } else {
+                Ok(raw as i64)
+            }
+        }
+        None => Err(Error::EINVAL),
+    }
+}
+
+// Like `duration_to_raw()`, mapping `None` to the special value `none`.
+fn optional_duration_to_raw(value: Option<Duration>, none: i64, unit_ms: u64, max: i64)
+                            -> Result<i64> {
+    match value {
+        Some(value) => duration_to_raw(value, unit_ms, max),
+        None => Ok(none),
+    }
+}
+
+// The inverse of `optional_duration_to_raw()`; negative values are
+// treated as `None` as well.
+fn raw_to_duration(raw: i64, none: i64, unit_ms: u64) -> Option<Duration> {
+    if raw == none || raw < 0 {
+        None
+    } else {
+        Some(Duration::from_millis(raw as u64 * unit_ms))
+    }
+}
+
+// Like `raw_to_duration()`, for options without a `None` value, where
+// a negative value cannot be represented and yields `EINVAL`.
+fn raw_to_required_duration(raw: i64, unit_ms: u64) -> Result<Duration> {
+    if raw < 0 {
+        return Err(Error::EINVAL);
+    }
+    (raw as u64).checked_mul(unit_ms).map(Duration::from_millis).ok_or(Error::EINVAL)
+}
+
 /// Poll for events on multiple sockets.
 ///
 /// For every poll item given, the events given in the `events` bitmask are
@@ -1017,6 +2125,15 @@ pub fn poll(items: &mut [PollItem], timeout: i64) -> Result<i32> {
     Ok(rc as i32)
 }
 
+/// Poll for events on multiple sockets, with a `Duration` timeout.
+///
+/// This is the same as `poll()`, with `None` indicating to block
+/// indefinitely. The timeout is rounded up to whole milliseconds.
+pub fn poll_duration(items: &mut [PollItem], timeout: Option<Duration>) -> Result<i32> {
+    let timeout = try!(optional_duration_to_raw(timeout, -1, 1, i64::max_value()));
+    poll(items, timeout)
+}
+
 /// Start a 0MQ proxy in the current thread.
 ///
 /// A proxy connects a frontend socket with a backend socket, where the exact
@@ -1041,6 +2158,38 @@ pub fn proxy_with_capture(frontend: &mut Socket,
     Ok(())
 }
 
+/// Start a 0MQ proxy in the current thread, which can be controlled
+/// through the `control` socket.
+///
+/// The proxy can be paused, resumed and terminated by sending the
+/// respective commands (`"PAUSE"`, `"RESUME"`, `"TERMINATE"`) to the
+/// control socket. See `spawn_proxy()` for a higher-level interface.
+///
+/// This function returns `Ok` when the proxy is terminated via the
+/// control socket.
+pub fn proxy_steerable(frontend: &mut Socket,
+                       backend: &mut Socket,
+                       control: &mut Socket) -> Result<()> {
+    zmq_try!(unsafe {
+        zmq_sys::zmq_proxy_steerable(frontend.sock, backend.sock, ptr::null_mut(), control.sock)
+    });
+    Ok(())
+}
+
+/// Start a 0MQ proxy in the current thread, with a capture socket,
+/// which can be controlled through the `control` socket.
+///
+/// See `proxy_steerable()` and `proxy_with_capture()`.
+pub fn proxy_steerable_with_capture(frontend: &mut Socket,
+                                    backend: &mut Socket,
+                                    capture: &mut Socket,
+                                    control: &mut Socket) -> Result<()> {
+    zmq_try!(unsafe {
+        zmq_sys::zmq_proxy_steerable(frontend.sock, backend.sock, capture.sock, control.sock)
+    });
+    Ok(())
+}
+
 /// Return true if the used 0MQ library has the given capability.
 ///
 /// For older versions of 0MQ that don't have the wrapped `zmq_has` function,
@@ -1049,7 +2198,11 @@ pub fn proxy_with_capture(frontend: &mut Socket,
 /// For a list of capabilities, please consult the `zmq_has` manual page.
 pub fn has(capability: &str) -> Option<bool> {
     if cfg!(ZMQ_HAS_ZMQ_HAS) {
-        let c_str = ffi::CString::new(capability).unwrap();
+        // No capability name contains a NUL byte.
+        let c_str = match ffi::CString::new(capability) {
+            Ok(c_str) => c_str,
+            Err(_) => return Some(false),
+        };
         unsafe {
             Some(zmq_sys::zmq_has(c_str.as_ptr()) == 1)
         }
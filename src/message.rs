@@ -1,14 +1,19 @@
 extern crate zmq_sys;
 
-use libc::{size_t};
+use libc::{c_int, size_t};
 
+use std::borrow::Cow;
 use std::ffi;
 use std::fmt;
-use std::{mem, ptr, str, slice};
+use std::alloc::{self, Layout};
+use std::{mem, process, ptr, str, slice};
 use std::os::raw::c_void;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
-use super::errno_to_error;
+use super::{errno_to_error, Result, SocketType};
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+use super::Error;
 
 /// Holds a 0MQ message.
 ///
@@ -22,6 +27,9 @@ use super::errno_to_error;
 /// operations in a loop more efficient, since allocated memory can be reused.
 pub struct Message {
     msg: zmq_sys::zmq_msg_t,
+    // Set if the message content must not be written to, as it is
+    // static or shared with its owner; `DerefMut` copies it first.
+    readonly: bool,
 }
 
 impl Drop for Message {
@@ -33,40 +41,64 @@ impl Drop for Message {
     }
 }
 
+// Message property for `zmq_msg_get`. Note that this differs from
+// the `ZMQ_MSG_SHARED` flag value, which is internal to libzmq.
+const ZMQ_SHARED: c_int = 3;
+
 impl fmt::Debug for Message {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self.deref())
     }
 }
 
-unsafe extern fn drop_msg_content_box(data: *mut c_void, _hint: *mut c_void) {
-    let _ = Box::from_raw(data as *mut u8);
+unsafe extern fn drop_msg_content_owner<T>(_data: *mut c_void, hint: *mut c_void) {
+    let _ = Box::from_raw(hint as *mut T);
 }
 
 impl Message {
 
-    unsafe fn alloc<F>(f: F) -> Message where F: FnOnce(&mut zmq_sys::zmq_msg_t) -> i32 {
+    unsafe fn try_alloc<F>(f: F) -> Result<Message>
+        where F: FnOnce(&mut zmq_sys::zmq_msg_t) -> i32
+    {
         let mut msg = zmq_sys::zmq_msg_t::default();
         let rc = f(&mut msg);
         if rc == -1 {
-            panic!(errno_to_error())
+            return Err(errno_to_error());
+        }
+        Ok(Message { msg: msg, readonly: false })
+    }
+
+    // Initializing a message can only fail if its `len` bytes of
+    // content cannot be allocated, which is handled like for `Vec`.
+    unsafe fn alloc<F>(len: usize, f: F) -> Message
+        where F: FnOnce(&mut zmq_sys::zmq_msg_t) -> i32
+    {
+        match Self::try_alloc(f) {
+            Ok(msg) => msg,
+            Err(_) => match Layout::array::<u8>(len) {
+                Ok(layout) => alloc::handle_alloc_error(layout),
+                Err(_) => process::abort(),
+            },
         }
-        Message { msg: msg }
     }
 
     /// Create an empty `Message`.
     pub fn new() -> Message {
-        unsafe {
-            Self::alloc(|msg| { zmq_sys::zmq_msg_init(msg) })
-        }
+        let mut msg = zmq_sys::zmq_msg_t::default();
+        // This only initializes the fields of `msg`, and cannot fail.
+        unsafe { zmq_sys::zmq_msg_init(&mut msg) };
+        Message { msg: msg, readonly: false }
     }
 
     /// Create a `Message` preallocated with `len` uninitialized bytes.
     pub unsafe fn with_capacity_unallocated(len: usize) -> Message {
-        Self::alloc(|msg| { zmq_sys::zmq_msg_init_size(msg, len as size_t) })
+        Self::alloc(len, |msg| { zmq_sys::zmq_msg_init_size(msg, len as size_t) })
     }
 
     /// Create a `Message` with space for `len` bytes that are initialized to 0.
+    ///
+    /// Like `Vec`, this aborts if the memory cannot be allocated; use
+    /// `try_with_capacity()` to handle this case.
     pub fn with_capacity(len: usize) -> Message {
         unsafe {
             let mut msg = Message::with_capacity_unallocated(len);
@@ -86,20 +118,87 @@ impl Message {
         }
     }
 
-    fn from_box(data: Box<[u8]>) -> Message {
-        let n = data.len();
-        if n == 0 {
-            return Message::new();
+    /// Create a `Message` with space for `len` bytes that are
+    /// initialized to 0, returning `Err(Error::ENOMEM)` if the memory
+    /// cannot be allocated.
+    pub fn try_with_capacity(len: usize) -> Result<Message> {
+        unsafe {
+            let mut msg = try!(Self::try_alloc(|msg| {
+                zmq_sys::zmq_msg_init_size(msg, len as size_t)
+            }));
+            ptr::write_bytes(msg.as_mut_ptr(), 0, len);
+            Ok(msg)
         }
-        let raw = Box::into_raw(data);
+    }
+
+    /// Create a `Message` from a `&[u8]`, returning `Err(Error::ENOMEM)`
+    /// if the memory cannot be allocated.
+    pub fn try_from_slice(data: &[u8]) -> Result<Message> {
         unsafe {
-            Self::alloc(|msg| {
+            let mut msg = try!(Self::try_alloc(|msg| {
+                zmq_sys::zmq_msg_init_size(msg, data.len() as size_t)
+            }));
+            ptr::copy_nonoverlapping(data.as_ptr(), msg.as_mut_ptr(), data.len());
+            Ok(msg)
+        }
+    }
+
+    /// Create a `Message` from `len` bytes at `data`, which are kept
+    /// alive by `owner` until libzmq releases the message content.
+    unsafe fn from_raw_owner<T>(owner: Box<T>, data: *mut u8, len: usize) -> Message {
+        if len == 0 {
+            return Message::new();
+        }
+        let hint = Box::into_raw(owner);
+        Self::alloc(len, |msg| {
+            zmq_sys::zmq_msg_init_data(
+                msg, data as *mut c_void, len,
+                drop_msg_content_owner::<T> as *mut zmq_sys::zmq_free_fn,
+                hint as *mut c_void)
+        })
+    }
+
+    /// Create a `Message` from any owned buffer, without copying it.
+    ///
+    /// The buffer is handed over to libzmq, and dropped once libzmq
+    /// no longer needs it, which may happen in a libzmq I/O thread.
+    /// Should the message content be mutated through `DerefMut`, it
+    /// is copied first, since `T` only grants shared access to the
+    /// buffer.
+    pub fn from_owner<T>(owner: T) -> Message
+        where T: AsRef<[u8]> + Send + 'static
+    {
+        let owner = Box::new(owner);
+        let (data, len) = {
+            let bytes = (*owner).as_ref();
+            (bytes.as_ptr() as *mut u8, bytes.len())
+        };
+        let mut msg = unsafe { Message::from_raw_owner(owner, data, len) };
+        msg.readonly = true;
+        msg
+    }
+
+    fn from_box(mut data: Box<[u8]>) -> Message {
+        let (ptr, len) = (data.as_mut_ptr(), data.len());
+        unsafe { Message::from_raw_owner(Box::new(data), ptr, len) }
+    }
+
+    /// Create a `Message` referring to static data, without copying it.
+    ///
+    /// The data is handed to libzmq as a constant buffer, so this is
+    /// cheap even for large payloads. Should the message content be
+    /// mutated through `DerefMut`, it is copied first.
+    pub fn from_static(data: &'static [u8]) -> Message {
+        let mut msg = unsafe {
+            Self::alloc(data.len(), |msg| {
+                // A null free function marks the data as constant.
                 zmq_sys::zmq_msg_init_data(
-                    msg, raw as *mut c_void, n,
-                    drop_msg_content_box as *mut zmq_sys::zmq_free_fn,
-                    ptr::null_mut())
+                    msg, data.as_ptr() as *mut c_void, data.len(),
+                    ptr::null_mut(), ptr::null_mut())
             })
-        }
+        };
+        msg.readonly = true;
+        msg
     }
 
     /// Return the message content as a string slice if it is valid UTF-8.
@@ -107,6 +206,93 @@ impl Message {
         str::from_utf8(self).ok()
     }
 
+    /// Return true if the message content is shared with other
+    /// messages, e.g. due to `share()`, or is constant data.
+    ///
+    /// Mutating a shared message through `DerefMut` copies its
+    /// content first. Note that this copy does not retain properties
+    /// such as `get_more()` or the metadata of a received message.
+    pub fn is_shared(&self) -> bool {
+        let rc = unsafe { zmq_sys::zmq_msg_get(&self.msg as *const _ as *mut _, ZMQ_SHARED) };
+        rc == 1
+    }
+
+    /// Create a message sharing the content of this one.
+    ///
+    /// For all but very small messages, libzmq shares the content
+    /// using a reference count instead of copying it, making this
+    /// cheap to do, e.g., for sending the same large payload to many
+    /// sockets.
+    ///
+    /// Unlike `clone()`, which copies the content, this takes `&mut
+    /// self`, since libzmq marks the source message as shared, and
+    /// returns a `Result`, as libzmq may reject the source message.
+    /// The new message also retains the properties of the original,
+    /// such as `get_more()`.
+    pub fn share(&mut self) -> Result<Message> {
+        let mut msg = Message::new();
+        if unsafe { zmq_sys::zmq_msg_copy(&mut msg.msg, &mut self.msg) } == -1 {
+            return Err(errno_to_error());
+        }
+        msg.readonly = self.readonly;
+        Ok(msg)
+    }
+
+    /// Return the routing id of a message received on a `SERVER`
+    /// socket, or `None` if it has not been set.
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    pub fn routing_id(&self) -> Option<u32> {
+        let routing_id = unsafe {
+            zmq_sys::zmq_msg_routing_id(&self.msg as *const _ as *mut _)
+        };
+        if routing_id == 0 {
+            None
+        } else {
+            Some(routing_id)
+        }
+    }
+
+    /// Set the routing id, which specifies the peer a message sent on
+    /// a `SERVER` socket is delivered to.
+    ///
+    /// The routing id must not be zero.
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    pub fn set_routing_id(&mut self, routing_id: u32) -> Result<()> {
+        zmq_try!(unsafe { zmq_sys::zmq_msg_set_routing_id(&mut self.msg, routing_id) });
+        Ok(())
+    }
+
+    /// Return the group of a message received on a `DISH` socket, or
+    /// `None` if it has not been set.
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    pub fn group(&self) -> Option<&str> {
+        let group = unsafe { zmq_sys::zmq_msg_group(&self.msg as *const _ as *mut _) };
+        if group.is_null() {
+            return None;
+        }
+        match unsafe { ffi::CStr::from_ptr(group).to_bytes() } {
+            b"" => None,
+            group => str::from_utf8(group).ok(),
+        }
+    }
+
+    /// Set the group a message sent on a `RADIO` socket is published
+    /// to.
+    ///
+    /// Group names are limited by libzmq to 15 bytes, or 255 bytes
+    /// since libzmq 4.3; longer names, or names with embedded NUL
+    /// bytes, result in `Error::EINVAL`.
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    pub fn set_group(&mut self, group: &str) -> Result<()> {
+        // libzmq 4.3 silently truncates longer names.
+        if cfg!(ZMQ_VERSION_4_3 = "1") && group.len() > 255 {
+            return Err(Error::EINVAL);
+        }
+        let c_str = try!(ffi::CString::new(group).map_err(|_| Error::EINVAL));
+        zmq_try!(unsafe { zmq_sys::zmq_msg_set_group(&mut self.msg, c_str.as_ptr()) });
+        Ok(())
+    }
+
     /// Return the `ZMQ_MORE` flag, which indicates if more parts of a multipart
     /// message will follow.
     pub fn get_more(&self) -> bool {
@@ -115,19 +301,152 @@ impl Message {
     }
 
     /// Query a message metadata property.
-    pub fn gets<'a>(&'a mut self, property: &str) -> Option<&'a str> {
-        let c_str = ffi::CString::new(property.as_bytes()).unwrap();
+    ///
+    /// Returns `None` if the property is not present, or if its value
+    /// is not valid UTF-8; use `gets_bytes()` for the raw value.
+    pub fn gets<'a>(&'a self, property: &str) -> Option<&'a str> {
+        self.gets_bytes(property).and_then(|value| str::from_utf8(value).ok())
+    }
+
+    /// Query a message metadata property, returning its raw value.
+    pub fn gets_bytes<'a>(&'a self, property: &str) -> Option<&'a [u8]> {
+        let c_str = match ffi::CString::new(property.as_bytes()) {
+            Ok(c_str) => c_str,
+            Err(_) => return None,
+        };
 
         let value = unsafe {
-            zmq_sys::zmq_msg_gets(&mut self.msg, c_str.as_ptr())
+            zmq_sys::zmq_msg_gets(&self.msg as *const _ as *mut _, c_str.as_ptr())
         };
 
         if value.is_null() {
             None
         } else {
-            Some(unsafe { str::from_utf8(ffi::CStr::from_ptr(value).to_bytes()).unwrap() })
+            Some(unsafe { ffi::CStr::from_ptr(value).to_bytes() })
+        }
+    }
+
+    /// Return the type of the socket the message was received from,
+    /// as given by the `Socket-Type` metadata property.
+    pub fn peer_socket_type(&self) -> Option<SocketType> {
+        self.gets(PROPERTY_SOCKET_TYPE).and_then(socket_type_from_name)
+    }
+
+    /// Return the routing id of the peer the message was received
+    /// from, as given by the `Routing-Id` (or, before ZMTP 3.1, the
+    /// `Identity`) metadata property.
+    pub fn peer_routing_id(&self) -> Option<&[u8]> {
+        self.gets_bytes(PROPERTY_ROUTING_ID).or_else(|| self.gets_bytes(PROPERTY_IDENTITY))
+    }
+
+    /// Return the user id of the peer, as provided by the ZAP handler
+    /// in the `User-Id` metadata property.
+    pub fn user_id(&self) -> Option<&str> {
+        self.gets(PROPERTY_USER_ID)
+    }
+
+    /// Return the IP address of the peer, as given by the
+    /// `Peer-Address` metadata property.
+    pub fn peer_address(&self) -> Option<&str> {
+        self.gets(PROPERTY_PEER_ADDRESS)
+    }
+
+    /// Query an application-defined metadata property.
+    ///
+    /// Application-defined properties are prefixed with `X-`, which
+    /// is added to `name` if not already present.
+    pub fn app_property<'a>(&'a self, name: &str) -> Option<&'a str> {
+        if name.starts_with("X-") {
+            self.gets(name)
+        } else {
+            self.gets(&format!("X-{}", name))
         }
     }
+
+    /// Iterate over the well-known metadata properties present on the
+    /// message, as `(name, value)` pairs.
+    pub fn properties(&self) -> Properties {
+        Properties {
+            msg: self,
+            names: KNOWN_PROPERTIES.iter(),
+        }
+    }
+}
+
+/// Name of the `Socket-Type` metadata property.
+pub const PROPERTY_SOCKET_TYPE: &'static str = "Socket-Type";
+/// Name of the `Identity` metadata property.
+pub const PROPERTY_IDENTITY: &'static str = "Identity";
+/// Name of the `Routing-Id` metadata property.
+pub const PROPERTY_ROUTING_ID: &'static str = "Routing-Id";
+/// Name of the `User-Id` metadata property.
+pub const PROPERTY_USER_ID: &'static str = "User-Id";
+/// Name of the `Peer-Address` metadata property.
+pub const PROPERTY_PEER_ADDRESS: &'static str = "Peer-Address";
+
+static KNOWN_PROPERTIES: [&'static str; 5] = [
+    PROPERTY_SOCKET_TYPE,
+    PROPERTY_IDENTITY,
+    PROPERTY_ROUTING_ID,
+    PROPERTY_USER_ID,
+    PROPERTY_PEER_ADDRESS,
+];
+
+fn socket_type_from_name(name: &str) -> Option<SocketType> {
+    match name {
+        "PAIR" => Some(SocketType::PAIR),
+        "PUB" => Some(SocketType::PUB),
+        "SUB" => Some(SocketType::SUB),
+        "REQ" => Some(SocketType::REQ),
+        "REP" => Some(SocketType::REP),
+        "DEALER" => Some(SocketType::DEALER),
+        "ROUTER" => Some(SocketType::ROUTER),
+        "PULL" => Some(SocketType::PULL),
+        "PUSH" => Some(SocketType::PUSH),
+        "XPUB" => Some(SocketType::XPUB),
+        "XSUB" => Some(SocketType::XSUB),
+        "STREAM" => Some(SocketType::STREAM),
+        #[cfg(feature = "draft")]
+        "SERVER" => Some(SocketType::SERVER),
+        #[cfg(feature = "draft")]
+        "CLIENT" => Some(SocketType::CLIENT),
+        #[cfg(feature = "draft")]
+        "RADIO" => Some(SocketType::RADIO),
+        #[cfg(feature = "draft")]
+        "DISH" => Some(SocketType::DISH),
+        #[cfg(feature = "draft")]
+        "GATHER" => Some(SocketType::GATHER),
+        #[cfg(feature = "draft")]
+        "SCATTER" => Some(SocketType::SCATTER),
+        #[cfg(feature = "draft")]
+        "DGRAM" => Some(SocketType::DGRAM),
+        #[cfg(feature = "draft")]
+        "PEER" => Some(SocketType::PEER),
+        #[cfg(feature = "draft")]
+        "CHANNEL" => Some(SocketType::CHANNEL),
+        _ => None,
+    }
+}
+
+/// Iterator over the well-known metadata properties of a `Message`.
+///
+/// This is returned by `Message::properties()`.
+pub struct Properties<'a> {
+    msg: &'a Message,
+    names: slice::Iter<'static, &'static str>,
+}
+
+impl<'a> Iterator for Properties<'a> {
+    type Item = (&'static str, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for name in &mut self.names {
+            if let Some(value) = self.msg.gets_bytes(name) {
+                return Some((name, value));
+            }
+        }
+        None
+    }
 }
 
 impl Deref for Message {
@@ -153,8 +472,22 @@ impl PartialEq for Message {
 
 impl Eq for Message {}
 
+/// Cloning copies the message content into a fresh, unshared message.
+///
+/// Like the copy made before mutating a shared message, the clone does
+/// not retain properties such as `get_more()` or the metadata of a
+/// received message. Use `share()` to avoid copying large payloads.
+impl Clone for Message {
+    fn clone(&self) -> Message {
+        Message::from_slice(self)
+    }
+}
+
 impl DerefMut for Message {
     fn deref_mut(&mut self) -> &mut [u8] {
+        if self.readonly || self.is_shared() {
+            *self = Message::from_slice(self);
+        }
         // This is safe because we're constraining the slice to the lifetime of
         // this message.
         unsafe {
@@ -179,16 +512,42 @@ impl From<Vec<u8>> for Message {
     }
 }
 
-impl<'a> From<&'a str> for Message {
-    /// Construct from a string slice by copying the UTF-8 data.
-    fn from(msg: &str) -> Self {
-        Message::from_slice(msg.as_bytes())
+impl From<Box<[u8]>> for Message {
+    /// Construct from a boxed slice without copying the data.
+    fn from(msg: Box<[u8]>) -> Self {
+        Message::from_box(msg)
     }
 }
 
-impl<'a> From<&'a String> for Message {
+impl From<Arc<[u8]>> for Message {
+    /// Construct from a reference-counted slice without copying the
+    /// data.
+    fn from(msg: Arc<[u8]>) -> Self {
+        Message::from_owner(msg)
+    }
+}
+
+impl From<String> for Message {
+    /// Construct from a string without copying the UTF-8 data.
+    fn from(msg: String) -> Self {
+        Message::from_box(msg.into_bytes().into_boxed_slice())
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for Message {
+    /// Construct from a `Cow`, copying the data only if it is
+    /// borrowed.
+    fn from(msg: Cow<'a, [u8]>) -> Self {
+        match msg {
+            Cow::Borrowed(data) => Message::from_slice(data),
+            Cow::Owned(data) => Message::from(data),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Message {
     /// Construct from a string slice by copying the UTF-8 data.
-    fn from(msg: &String) -> Self {
+    fn from(msg: &str) -> Self {
         Message::from_slice(msg.as_bytes())
     }
 }
@@ -201,7 +560,12 @@ impl<'a, T> From<&'a T> for Message
     }
 }
 
-/// Get the low-level C pointer.
+/// Get the low-level C pointer, for passing the message to
+/// `zmq_msg_send` or `zmq_msg_recv`.
+///
+/// Both of these replace the message content, so it is no longer
+/// considered read-only afterwards.
 pub fn msg_ptr(msg: &mut Message) -> *mut zmq_sys::zmq_msg_t {
+    msg.readonly = false;
     &mut msg.msg
 }
@@ -0,0 +1,209 @@
+//! Socket monitoring, built on top of `zmq_socket_monitor`.
+
+extern crate zmq_sys;
+
+use std::ptr;
+
+use zmq_sys::RawFd;
+
+use super::{AsEndpoint, Context, Error, Message, PollEvents, PollItem, Result, Socket, PAIR};
+
+bitflags! {
+    /// Set of events that can be requested from `Socket::monitor()`.
+    pub flags MonitorEvents: i32 {
+        /// The socket has successfully connected to a remote peer.
+        const EVENT_CONNECTED                 = 0x0001,
+        /// A connect request on the socket is pending.
+        const EVENT_CONNECT_DELAYED           = 0x0002,
+        /// A connect request failed, and is now being retried.
+        const EVENT_CONNECT_RETRIED           = 0x0004,
+        /// The socket was successfully bound to a network interface.
+        const EVENT_LISTENING                 = 0x0008,
+        /// The socket could not bind to a given interface.
+        const EVENT_BIND_FAILED               = 0x0010,
+        /// The socket has accepted a connection from a remote peer.
+        const EVENT_ACCEPTED                  = 0x0020,
+        /// The socket has rejected a connection from a remote peer.
+        const EVENT_ACCEPT_FAILED             = 0x0040,
+        /// The socket was closed.
+        const EVENT_CLOSED                    = 0x0080,
+        /// The socket close failed.
+        const EVENT_CLOSE_FAILED              = 0x0100,
+        /// The socket was disconnected unexpectedly.
+        const EVENT_DISCONNECTED              = 0x0200,
+        /// Monitoring on this socket ended.
+        const EVENT_MONITOR_STOPPED           = 0x0400,
+        /// The ZMTP security handshake failed, without further detail.
+        const EVENT_HANDSHAKE_FAILED_NO_DETAIL = 0x0800,
+        /// The ZMTP security handshake succeeded.
+        const EVENT_HANDSHAKE_SUCCEEDED       = 0x1000,
+        /// The ZMTP security handshake failed due to a protocol error.
+        const EVENT_HANDSHAKE_FAILED_PROTOCOL = 0x2000,
+        /// The ZMTP security handshake failed due to an authentication
+        /// failure.
+        const EVENT_HANDSHAKE_FAILED_AUTH     = 0x4000,
+        /// All of the above.
+        const EVENT_ALL                       = 0xFFFF,
+    }
+}
+
+/// An event reported by a socket monitor.
+///
+/// Each event carries the endpoint it refers to, along with the
+/// event-specific value transmitted by libzmq: a file descriptor, an
+/// error number, or a reconnect interval.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SocketEvent {
+    Connected { endpoint: String, fd: RawFd },
+    ConnectDelayed { endpoint: String, errno: i32 },
+    ConnectRetried { endpoint: String, interval: i32 },
+    Listening { endpoint: String, fd: RawFd },
+    BindFailed { endpoint: String, errno: i32 },
+    Accepted { endpoint: String, fd: RawFd },
+    AcceptFailed { endpoint: String, errno: i32 },
+    Closed { endpoint: String, fd: RawFd },
+    CloseFailed { endpoint: String, errno: i32 },
+    Disconnected { endpoint: String, fd: RawFd },
+    MonitorStopped { endpoint: String },
+    HandshakeFailedNoDetail { endpoint: String, errno: i32 },
+    HandshakeSucceeded { endpoint: String },
+    /// The value is one of the `ZMQ_PROTOCOL_ERROR_*` codes.
+    HandshakeFailedProtocol { endpoint: String, code: i32 },
+    /// The value is the ZAP status code (e.g. 400).
+    HandshakeFailedAuth { endpoint: String, status: i32 },
+}
+
+impl SocketEvent {
+    /// Construct an event from its raw parts, as sent over the wire by
+    /// libzmq.
+    ///
+    /// Returns `Err(Error::EPROTO)` for an unknown event number.
+    pub fn from_raw(event: u16, value: u32, endpoint: String) -> Result<SocketEvent> {
+        let ev = match event {
+            0x0001 => SocketEvent::Connected { endpoint: endpoint, fd: value as RawFd },
+            0x0002 => SocketEvent::ConnectDelayed { endpoint: endpoint, errno: value as i32 },
+            0x0004 => SocketEvent::ConnectRetried { endpoint: endpoint, interval: value as i32 },
+            0x0008 => SocketEvent::Listening { endpoint: endpoint, fd: value as RawFd },
+            0x0010 => SocketEvent::BindFailed { endpoint: endpoint, errno: value as i32 },
+            0x0020 => SocketEvent::Accepted { endpoint: endpoint, fd: value as RawFd },
+            0x0040 => SocketEvent::AcceptFailed { endpoint: endpoint, errno: value as i32 },
+            0x0080 => SocketEvent::Closed { endpoint: endpoint, fd: value as RawFd },
+            0x0100 => SocketEvent::CloseFailed { endpoint: endpoint, errno: value as i32 },
+            0x0200 => SocketEvent::Disconnected { endpoint: endpoint, fd: value as RawFd },
+            0x0400 => SocketEvent::MonitorStopped { endpoint: endpoint },
+            0x0800 => SocketEvent::HandshakeFailedNoDetail { endpoint: endpoint, errno: value as i32 },
+            0x1000 => SocketEvent::HandshakeSucceeded { endpoint: endpoint },
+            0x2000 => SocketEvent::HandshakeFailedProtocol { endpoint: endpoint, code: value as i32 },
+            0x4000 => SocketEvent::HandshakeFailedAuth { endpoint: endpoint, status: value as i32 },
+            _ => return Err(Error::EPROTO),
+        };
+        Ok(ev)
+    }
+
+    /// Decode an event from the two frames sent by libzmq.
+    ///
+    /// The first frame contains the event number (16 bits) followed
+    /// by the event value (32 bits), both in native byte order. The
+    /// second frame contains the affected endpoint.
+    pub fn decode(event_frame: &[u8], endpoint_frame: &[u8]) -> Result<SocketEvent> {
+        if event_frame.len() != 6 {
+            return Err(Error::EPROTO);
+        }
+        let (event, value) = unsafe {
+            (ptr::read_unaligned(event_frame.as_ptr() as *const u16),
+             ptr::read_unaligned(event_frame[2..].as_ptr() as *const u32))
+        };
+        let endpoint = String::from_utf8_lossy(endpoint_frame).into_owned();
+        SocketEvent::from_raw(event, value, endpoint)
+    }
+
+    /// Return the kind of this event, as a single `MonitorEvents` flag.
+    pub fn kind(&self) -> MonitorEvents {
+        match *self {
+            SocketEvent::Connected { .. } => EVENT_CONNECTED,
+            SocketEvent::ConnectDelayed { .. } => EVENT_CONNECT_DELAYED,
+            SocketEvent::ConnectRetried { .. } => EVENT_CONNECT_RETRIED,
+            SocketEvent::Listening { .. } => EVENT_LISTENING,
+            SocketEvent::BindFailed { .. } => EVENT_BIND_FAILED,
+            SocketEvent::Accepted { .. } => EVENT_ACCEPTED,
+            SocketEvent::AcceptFailed { .. } => EVENT_ACCEPT_FAILED,
+            SocketEvent::Closed { .. } => EVENT_CLOSED,
+            SocketEvent::CloseFailed { .. } => EVENT_CLOSE_FAILED,
+            SocketEvent::Disconnected { .. } => EVENT_DISCONNECTED,
+            SocketEvent::MonitorStopped { .. } => EVENT_MONITOR_STOPPED,
+            SocketEvent::HandshakeFailedNoDetail { .. } => EVENT_HANDSHAKE_FAILED_NO_DETAIL,
+            SocketEvent::HandshakeSucceeded { .. } => EVENT_HANDSHAKE_SUCCEEDED,
+            SocketEvent::HandshakeFailedProtocol { .. } => EVENT_HANDSHAKE_FAILED_PROTOCOL,
+            SocketEvent::HandshakeFailedAuth { .. } => EVENT_HANDSHAKE_FAILED_AUTH,
+        }
+    }
+
+    /// Return the endpoint this event refers to.
+    pub fn endpoint(&self) -> &str {
+        match *self {
+            SocketEvent::Connected { ref endpoint, .. } |
+            SocketEvent::ConnectDelayed { ref endpoint, .. } |
+            SocketEvent::ConnectRetried { ref endpoint, .. } |
+            SocketEvent::Listening { ref endpoint, .. } |
+            SocketEvent::BindFailed { ref endpoint, .. } |
+            SocketEvent::Accepted { ref endpoint, .. } |
+            SocketEvent::AcceptFailed { ref endpoint, .. } |
+            SocketEvent::Closed { ref endpoint, .. } |
+            SocketEvent::CloseFailed { ref endpoint, .. } |
+            SocketEvent::Disconnected { ref endpoint, .. } |
+            SocketEvent::MonitorStopped { ref endpoint } |
+            SocketEvent::HandshakeFailedNoDetail { ref endpoint, .. } |
+            SocketEvent::HandshakeSucceeded { ref endpoint } |
+            SocketEvent::HandshakeFailedProtocol { ref endpoint, .. } |
+            SocketEvent::HandshakeFailedAuth { ref endpoint, .. } => endpoint,
+        }
+    }
+}
+
+/// Receiving end of a socket monitor.
+///
+/// A `Monitor` wraps the `PAIR` socket connected to the endpoint
+/// passed to `Socket::monitor()`, and decodes the received frames into
+/// `SocketEvent`s.
+pub struct Monitor {
+    socket: Socket,
+}
+
+impl Monitor {
+    /// Connect to the monitor endpoint of a socket.
+    ///
+    /// `Socket::monitor()` must have been called with `endpoint`
+    /// before, since the monitor endpoint is bound by libzmq.
+    pub fn connect<E: ?Sized + AsEndpoint>(ctx: &Context, endpoint: &E) -> Result<Monitor> {
+        let socket = try!(ctx.socket(PAIR));
+        try!(socket.connect(endpoint));
+        Ok(Monitor { socket: socket })
+    }
+
+    /// Receive and decode the next event.
+    ///
+    /// With `DONTWAIT`, this returns `Err(Error::EAGAIN)` if no event
+    /// is pending.
+    pub fn recv_event(&self, flags: i32) -> Result<SocketEvent> {
+        let mut event = Message::new();
+        try!(self.socket.recv(&mut event, flags));
+        if !event.get_more() {
+            return Err(Error::EPROTO);
+        }
+        // The endpoint frame is sent atomically with the event frame,
+        // so there is no need to honor `DONTWAIT` here.
+        let mut endpoint = Message::new();
+        try!(self.socket.recv(&mut endpoint, 0));
+        SocketEvent::decode(&event, &endpoint)
+    }
+
+    /// Create a `PollItem` for the underlying `PAIR` socket.
+    pub fn as_poll_item(&self, events: PollEvents) -> PollItem {
+        self.socket.as_poll_item(events)
+    }
+
+    /// Return a reference to the underlying `PAIR` socket.
+    pub fn socket(&self) -> &Socket {
+        &self.socket
+    }
+}
@@ -0,0 +1,246 @@
+//! Declarative socket configuration.
+
+use std::error;
+use std::fmt;
+use std::result;
+
+use super::{Error, Socket};
+#[cfg(all(ZMQ_VERSION_4_3 = "1", ZMQ_HAS_DRAFT = "1"))]
+use super::SocketType;
+
+/// Error returned by `Socket::apply()` and `Socket::options()`.
+///
+/// Carries the name of the option that could not be set or read, which
+/// is the same as the corresponding `SocketOptions` field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SocketOptionError {
+    pub option: &'static str,
+    pub error: Error,
+}
+
+impl fmt::Display for SocketOptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "socket option `{}`: {}", self.option, self.error)
+    }
+}
+
+impl error::Error for SocketOptionError {
+    fn description(&self) -> &str {
+        "socket option error"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        Some(&self.error)
+    }
+}
+
+impl From<SocketOptionError> for Error {
+    fn from(err: SocketOptionError) -> Self {
+        err.error
+    }
+}
+
+macro_rules! socket_options {
+    ( $( $(#[$attr:meta])* $field:ident: $ty:ty => ($getter:ident, $setter:ident), )* ) => {
+        /// A set of socket options, to be applied to or read from a
+        /// socket in one go.
+        ///
+        /// Each field corresponds to the `Socket` accessors of the same
+        /// name; fields set to `None` are left alone by
+        /// `Socket::apply()`. Options introduced in later libzmq
+        /// versions are only present if the linked libzmq supports them.
+        ///
+        /// Credentials, such as the PLAIN password, and CURVE keys are
+        /// deliberately not included, so a snapshot can be logged
+        /// safely. Applying a snapshot of a `curve_server` socket thus
+        /// requires setting its secret key separately.
+        #[derive(Clone, Debug, Default, PartialEq)]
+        pub struct SocketOptions {
+            $( $(#[$attr])* pub $field: Option<$ty>, )*
+            pub identity: Option<Vec<u8>>,
+            pub zap_domain: Option<String>,
+            pub socks_proxy: Option<String>,
+            #[cfg(ZMQ_VERSION_4_3 = "1")]
+            pub bindtodevice: Option<String>,
+        }
+
+        impl Socket {
+            /// Set all options present in `options`.
+            ///
+            /// Options are set in the order of the `SocketOptions`
+            /// fields. On failure, the remaining options are not
+            /// applied, and the error names the failed option.
+            pub fn apply(&self, options: &SocketOptions) -> result::Result<(), SocketOptionError> {
+                $(
+                    $(#[$attr])*
+                    {
+                        if let Some(value) = options.$field {
+                            try!(self.$setter(value).map_err(|e| option_error(stringify!($field), e)));
+                        }
+                    }
+                )*
+                if let Some(ref value) = options.identity {
+                    try!(self.set_identity(value).map_err(|e| option_error("identity", e)));
+                }
+                if let Some(ref value) = options.zap_domain {
+                    try!(self.set_zap_domain(value).map_err(|e| option_error("zap_domain", e)));
+                }
+                if let Some(ref value) = options.socks_proxy {
+                    // libzmq only accepts clearing the proxy via NULL.
+                    let value = if value.is_empty() { None } else { Some(value.as_str()) };
+                    try!(self.set_socks_proxy(value).map_err(|e| option_error("socks_proxy", e)));
+                }
+                #[cfg(ZMQ_VERSION_4_3 = "1")]
+                {
+                    if let Some(ref value) = options.bindtodevice {
+                        try!(self.set_bindtodevice(value)
+                                 .map_err(|e| option_error("bindtodevice", e)));
+                    }
+                }
+                Ok(())
+            }
+
+            /// Read all options supported by `SocketOptions`.
+            ///
+            /// Options that the linked libzmq cannot report (i.e. that
+            /// fail with `Error::EINVAL`) are left as `None`, as are
+            /// `identity`, `zap_domain` and `bindtodevice` if none has
+            /// been set, `heartbeat_timeout` if it defaults to the
+            /// heartbeat interval, and `router_notify` for sockets
+            /// other than `ROUTER`, so the snapshot can always be
+            /// applied. The `*_server` options are only reported when
+            /// enabled, as disabling one would reset the mechanism
+            /// selected by another. String options that are not valid
+            /// UTF-8 are reported as `Error::EINVAL`.
+            pub fn options(&self) -> result::Result<SocketOptions, SocketOptionError> {
+                let mut options = SocketOptions::default();
+                $(
+                    $(#[$attr])*
+                    {
+                        options.$field = match self.$getter() {
+                            Ok(value) => Some(value),
+                            Err(Error::EINVAL) => None,
+                            Err(e) => return Err(option_error(stringify!($field), e)),
+                        };
+                    }
+                )*
+                // The default of `-1` stands for the heartbeat interval,
+                // but libzmq does not accept it as a value.
+                #[cfg(ZMQ_VERSION_4_2 = "1")]
+                {
+                    if options.heartbeat_timeout == Some(-1) {
+                        options.heartbeat_timeout = None;
+                    }
+                }
+                // libzmq reports this option for any socket type, but
+                // only accepts it for `ROUTER` sockets.
+                #[cfg(all(ZMQ_VERSION_4_3 = "1", ZMQ_HAS_DRAFT = "1"))]
+                {
+                    if self.get_socket_type() != Ok(SocketType::ROUTER) {
+                        options.router_notify = None;
+                    }
+                }
+                if options.plain_server == Some(false) {
+                    options.plain_server = None;
+                }
+                #[cfg(ZMQ_HAS_CURVE = "1")]
+                {
+                    if options.curve_server == Some(false) {
+                        options.curve_server = None;
+                    }
+                }
+                #[cfg(ZMQ_HAS_GSSAPI = "1")]
+                {
+                    if options.gssapi_server == Some(false) {
+                        options.gssapi_server = None;
+                    }
+                }
+                let identity = try!(self.get_identity().map_err(|e| option_error("identity", e)));
+                if !identity.is_empty() {
+                    options.identity = Some(identity);
+                }
+                let zap_domain = try!(utf8_option("zap_domain", self.get_zap_domain()));
+                if !zap_domain.is_empty() {
+                    options.zap_domain = Some(zap_domain);
+                }
+                options.socks_proxy = Some(try!(utf8_option("socks_proxy", self.get_socks_proxy())));
+                #[cfg(ZMQ_VERSION_4_3 = "1")]
+                {
+                    let device = try!(utf8_option("bindtodevice", self.get_bindtodevice()));
+                    if !device.is_empty() {
+                        options.bindtodevice = Some(device);
+                    }
+                }
+                Ok(options)
+            }
+        }
+    };
+}
+
+fn option_error(option: &'static str, error: Error) -> SocketOptionError {
+    SocketOptionError {
+        option: option,
+        error: error,
+    }
+}
+
+fn utf8_option(option: &'static str, value: super::Result<result::Result<String, Vec<u8>>>)
+               -> result::Result<String, SocketOptionError> {
+    match value {
+        Ok(Ok(s)) => Ok(s),
+        Ok(Err(_)) => Err(option_error(option, Error::EINVAL)),
+        Err(e) => Err(option_error(option, e)),
+    }
+}
+
+socket_options! {
+    ipv6: bool => (is_ipv6, set_ipv6),
+    immediate: bool => (is_immediate, set_immediate),
+    conflate: bool => (is_conflate, set_conflate),
+    maxmsgsize: i64 => (get_maxmsgsize, set_maxmsgsize),
+    sndhwm: i32 => (get_sndhwm, set_sndhwm),
+    rcvhwm: i32 => (get_rcvhwm, set_rcvhwm),
+    affinity: u64 => (get_affinity, set_affinity),
+    rate: i32 => (get_rate, set_rate),
+    recovery_ivl: i32 => (get_recovery_ivl, set_recovery_ivl),
+    sndbuf: i32 => (get_sndbuf, set_sndbuf),
+    rcvbuf: i32 => (get_rcvbuf, set_rcvbuf),
+    tos: i32 => (get_tos, set_tos),
+    linger: i32 => (get_linger, set_linger),
+    reconnect_ivl: i32 => (get_reconnect_ivl, set_reconnect_ivl),
+    reconnect_ivl_max: i32 => (get_reconnect_ivl_max, set_reconnect_ivl_max),
+    backlog: i32 => (get_backlog, set_backlog),
+    multicast_hops: i32 => (get_multicast_hops, set_multicast_hops),
+    rcvtimeo: i32 => (get_rcvtimeo, set_rcvtimeo),
+    sndtimeo: i32 => (get_sndtimeo, set_sndtimeo),
+    tcp_keepalive: i32 => (get_tcp_keepalive, set_tcp_keepalive),
+    tcp_keepalive_cnt: i32 => (get_tcp_keepalive_cnt, set_tcp_keepalive_cnt),
+    tcp_keepalive_idle: i32 => (get_tcp_keepalive_idle, set_tcp_keepalive_idle),
+    tcp_keepalive_intvl: i32 => (get_tcp_keepalive_intvl, set_tcp_keepalive_intvl),
+    handshake_ivl: i32 => (get_handshake_ivl, set_handshake_ivl),
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    heartbeat_ivl: i32 => (get_heartbeat_ivl, set_heartbeat_ivl),
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    heartbeat_ttl: i32 => (get_heartbeat_ttl, set_heartbeat_ttl),
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    heartbeat_timeout: i32 => (get_heartbeat_timeout, set_heartbeat_timeout),
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    connect_timeout: i32 => (get_connect_timeout, set_connect_timeout),
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    tcp_maxrt: i32 => (get_tcp_maxrt, set_tcp_maxrt),
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    multicast_maxtpdu: i32 => (get_multicast_maxtpdu, set_multicast_maxtpdu),
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    invert_matching: bool => (is_invert_matching, set_invert_matching),
+    #[cfg(all(ZMQ_VERSION_4_3 = "1", ZMQ_HAS_DRAFT = "1"))]
+    router_notify: i32 => (get_router_notify, set_router_notify),
+    #[cfg(all(ZMQ_VERSION_4_3 = "1", ZMQ_HAS_DRAFT = "1"))]
+    zap_enforce_domain: bool => (is_zap_enforce_domain, set_zap_enforce_domain),
+    #[cfg(all(ZMQ_VERSION_4_3 = "1", ZMQ_HAS_DRAFT = "1"))]
+    loopback_fastpath: bool => (is_loopback_fastpath, set_loopback_fastpath),
+    plain_server: bool => (is_plain_server, set_plain_server),
+    #[cfg(ZMQ_HAS_CURVE = "1")]
+    curve_server: bool => (is_curve_server, set_curve_server),
+    #[cfg(ZMQ_HAS_GSSAPI = "1")]
+    gssapi_server: bool => (is_gssapi_server, set_gssapi_server),
+}
@@ -0,0 +1,295 @@
+//! Owned pollers, built on top of `zmq_poller` if available.
+
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+extern crate zmq_sys;
+
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+use libc::{c_int, c_long, c_short};
+
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+use std::os::raw::c_void;
+use std::time::Duration;
+use std::vec;
+
+use zmq_sys::RawFd;
+
+use super::{optional_duration_to_raw, poll, Error, PollEvents, Result, Socket};
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+use super::errno_to_error;
+#[cfg(not(ZMQ_HAS_DRAFT = "1"))]
+use super::PollItem;
+
+#[derive(Clone, Copy)]
+enum Target<'a> {
+    Socket(&'a Socket),
+    Fd(RawFd),
+}
+
+impl<'a> PartialEq for Target<'a> {
+    fn eq(&self, other: &Target<'a>) -> bool {
+        match (*self, *other) {
+            (Target::Socket(a), Target::Socket(b)) => a.sock == b.sock,
+            (Target::Fd(a), Target::Fd(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+struct Registration<'a> {
+    token: usize,
+    target: Target<'a>,
+    events: PollEvents,
+}
+
+/// A set of sockets and file descriptors to wait on.
+///
+/// Unlike `poll()`, a `Poller` owns its registrations, which can be
+/// added, modified and removed between calls to `wait()`. Each
+/// registration is identified by a user-chosen token, which is used to
+/// report the events that occurred.
+///
+/// When libzmq provides the draft `zmq_poller` API, it is used;
+/// otherwise, `wait()` is implemented using `zmq_poll`.
+pub struct Poller<'a> {
+    registrations: Vec<Registration<'a>>,
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    poller: *mut c_void,
+}
+
+impl<'a> Poller<'a> {
+    /// Create an empty poller.
+    ///
+    /// This only fails if libzmq cannot allocate its poller.
+    pub fn new() -> Result<Poller<'a>> {
+        Ok(Poller {
+            registrations: Vec::new(),
+            #[cfg(ZMQ_HAS_DRAFT = "1")]
+            poller: {
+                let poller = unsafe { zmq_sys::zmq_poller_new() };
+                if poller.is_null() {
+                    return Err(errno_to_error());
+                }
+                poller
+            },
+        })
+    }
+
+    /// Register a socket, to wait for the given `events` on it.
+    ///
+    /// Returns `Err(Error::EINVAL)` if either the token or the socket
+    /// is already registered.
+    pub fn add_socket(&mut self, socket: &'a Socket, token: usize, events: PollEvents)
+                      -> Result<()> {
+        self.add(Target::Socket(socket), token, events)
+    }
+
+    /// Register a non-0MQ socket, given by its file descriptor.
+    ///
+    /// Returns `Err(Error::EINVAL)` if either the token or the file
+    /// descriptor is already registered.
+    pub fn add_fd(&mut self, fd: RawFd, token: usize, events: PollEvents) -> Result<()> {
+        self.add(Target::Fd(fd), token, events)
+    }
+
+    fn add(&mut self, target: Target<'a>, token: usize, events: PollEvents) -> Result<()> {
+        if self.registrations.iter().any(|r| r.token == token || r.target == target) {
+            return Err(Error::EINVAL);
+        }
+        try!(self.raw_add(target, token, events));
+        self.registrations.push(Registration {
+            token: token,
+            target: target,
+            events: events,
+        });
+        Ok(())
+    }
+
+    /// Change the events to wait for on a registration.
+    ///
+    /// Returns `Err(Error::EINVAL)` if the token is not registered.
+    pub fn modify(&mut self, token: usize, events: PollEvents) -> Result<()> {
+        let index = try!(self.position(token));
+        try!(self.raw_modify(self.registrations[index].target, events));
+        self.registrations[index].events = events;
+        Ok(())
+    }
+
+    /// Remove a registration.
+    ///
+    /// Returns `Err(Error::EINVAL)` if the token is not registered.
+    pub fn remove(&mut self, token: usize) -> Result<()> {
+        let index = try!(self.position(token));
+        try!(self.raw_remove(self.registrations[index].target));
+        self.registrations.remove(index);
+        Ok(())
+    }
+
+    /// Return the number of registrations.
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    /// Return true if there are no registrations.
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    /// Wait for events on the registered sockets and file descriptors.
+    ///
+    /// The timeout is in milliseconds, with the same meaning as for
+    /// `poll()`. The returned iterator yields the token and occurred
+    /// events of each registration that has events signaled; if the
+    /// timeout expires, it is empty.
+    pub fn wait(&mut self, timeout: i64) -> Result<PollerEvents> {
+        if self.registrations.is_empty() {
+            try!(poll(&mut [], timeout));
+            return Ok(PollerEvents { events: Vec::new().into_iter() });
+        }
+        let events = try!(self.raw_wait(timeout));
+        Ok(PollerEvents { events: events.into_iter() })
+    }
+
+    /// Wait for events, with a `Duration` timeout.
+    ///
+    /// This is the same as `wait()`, with `None` indicating to block
+    /// indefinitely. The timeout is rounded up to whole milliseconds.
+    pub fn wait_duration(&mut self, timeout: Option<Duration>) -> Result<PollerEvents> {
+        let timeout = try!(optional_duration_to_raw(timeout, -1, 1, i64::max_value()));
+        self.wait(timeout)
+    }
+
+    fn position(&self, token: usize) -> Result<usize> {
+        self.registrations.iter().position(|r| r.token == token).ok_or(Error::EINVAL)
+    }
+
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    fn raw_add(&mut self, target: Target<'a>, token: usize, events: PollEvents) -> Result<()> {
+        let user_data = token as *mut c_void;
+        zmq_try!(unsafe {
+            match target {
+                Target::Socket(socket) => {
+                    zmq_sys::zmq_poller_add(self.poller, socket.sock, user_data,
+                                            events.bits() as c_short)
+                }
+                Target::Fd(fd) => {
+                    zmq_sys::zmq_poller_add_fd(self.poller, fd as c_int, user_data,
+                                               events.bits() as c_short)
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    fn raw_modify(&mut self, target: Target<'a>, events: PollEvents) -> Result<()> {
+        zmq_try!(unsafe {
+            match target {
+                Target::Socket(socket) => {
+                    zmq_sys::zmq_poller_modify(self.poller, socket.sock, events.bits() as c_short)
+                }
+                Target::Fd(fd) => {
+                    zmq_sys::zmq_poller_modify_fd(self.poller, fd as c_int,
+                                                  events.bits() as c_short)
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    fn raw_remove(&mut self, target: Target<'a>) -> Result<()> {
+        zmq_try!(unsafe {
+            match target {
+                Target::Socket(socket) => zmq_sys::zmq_poller_remove(self.poller, socket.sock),
+                Target::Fd(fd) => zmq_sys::zmq_poller_remove_fd(self.poller, fd as c_int),
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(ZMQ_HAS_DRAFT = "1")]
+    fn raw_wait(&mut self, timeout: i64) -> Result<Vec<(usize, PollEvents)>> {
+        let mut events = vec![zmq_sys::zmq_poller_event_t::default(); self.registrations.len()];
+        let rc = unsafe {
+            zmq_sys::zmq_poller_wait_all(self.poller,
+                                         events.as_mut_ptr(),
+                                         events.len() as c_int,
+                                         timeout as c_long)
+        };
+        if rc == -1 {
+            // `zmq_poller` signals an expired timeout as an error.
+            return match errno_to_error() {
+                Error::EAGAIN => Ok(Vec::new()),
+                e => Err(e),
+            };
+        }
+        Ok(events[..rc as usize].iter().map(|event| {
+            (event.user_data as usize, PollEvents::from_bits_truncate(event.events))
+        }).collect())
+    }
+
+    #[cfg(not(ZMQ_HAS_DRAFT = "1"))]
+    fn raw_add(&mut self, _target: Target<'a>, _token: usize, _events: PollEvents) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(not(ZMQ_HAS_DRAFT = "1"))]
+    fn raw_modify(&mut self, _target: Target<'a>, _events: PollEvents) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(not(ZMQ_HAS_DRAFT = "1"))]
+    fn raw_remove(&mut self, _target: Target<'a>) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(not(ZMQ_HAS_DRAFT = "1"))]
+    fn raw_wait(&mut self, timeout: i64) -> Result<Vec<(usize, PollEvents)>> {
+        let mut items: Vec<PollItem> = self.registrations.iter().map(|r| {
+            match r.target {
+                Target::Socket(socket) => socket.as_poll_item(r.events),
+                Target::Fd(fd) => PollItem::from_fd(fd, r.events),
+            }
+        }).collect();
+        try!(poll(&mut items, timeout));
+        Ok(self.registrations.iter().zip(items.iter()).filter_map(|(r, item)| {
+            let revents = item.get_revents();
+            if revents.is_empty() {
+                None
+            } else {
+                Some((r.token, revents))
+            }
+        }).collect())
+    }
+}
+
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+impl<'a> Drop for Poller<'a> {
+    fn drop(&mut self) {
+        if unsafe { zmq_sys::zmq_poller_destroy(&mut self.poller) } == -1 {
+            error!("failed to destroy poller: {}", errno_to_error());
+        }
+    }
+}
+
+/// Iterator over the events returned by `Poller::wait()`.
+///
+/// Yields the token of each registration that has events signaled,
+/// together with these events.
+pub struct PollerEvents {
+    events: vec::IntoIter<(usize, PollEvents)>,
+}
+
+impl Iterator for PollerEvents {
+    type Item = (usize, PollEvents);
+
+    fn next(&mut self) -> Option<(usize, PollEvents)> {
+        self.events.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.events.size_hint()
+    }
+}
+
+impl ExactSizeIterator for PollerEvents {}
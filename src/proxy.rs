@@ -0,0 +1,161 @@
+//! Steerable proxies, built on top of `zmq_proxy_steerable`.
+
+use std::panic;
+#[cfg(ZMQ_VERSION_4_2 = "1")]
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::thread;
+
+use super::{proxy_steerable, proxy_steerable_with_capture};
+use super::{Context, Result, Socket, PAIR};
+#[cfg(ZMQ_VERSION_4_2 = "1")]
+use super::Error;
+
+static CONTROL_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Message and byte counters of a steerable proxy.
+///
+/// "Received" counts refer to messages read from the respective
+/// socket, "sent" counts to messages written to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProxyStatistics {
+    pub frontend_messages_received: u64,
+    pub frontend_bytes_received: u64,
+    pub frontend_messages_sent: u64,
+    pub frontend_bytes_sent: u64,
+    pub backend_messages_received: u64,
+    pub backend_bytes_received: u64,
+    pub backend_messages_sent: u64,
+    pub backend_bytes_sent: u64,
+}
+
+/// Handle for a proxy running in a background thread.
+///
+/// This is returned by `spawn_proxy()`. Dropping the handle
+/// terminates the proxy.
+pub struct ProxyControl {
+    control: Socket,
+    thread: Option<thread::JoinHandle<Result<()>>>,
+}
+
+/// Start a steerable 0MQ proxy in a background thread.
+///
+/// The proxy connects `frontend` with `backend` (and optionally
+/// `capture`) just like `proxy()` does, but can be paused, resumed and
+/// terminated using the returned `ProxyControl` handle. The sockets
+/// are closed when the proxy terminates.
+pub fn spawn_proxy(ctx: &Context,
+                   frontend: Socket,
+                   backend: Socket,
+                   capture: Option<Socket>) -> Result<ProxyControl> {
+    let endpoint = format!("inproc://zmq-proxy-control-{}",
+                           CONTROL_ID.fetch_add(1, Ordering::SeqCst));
+    // Bind in this thread, so the endpoint is guaranteed to exist
+    // before we connect to it.
+    let steer = try!(ctx.socket(PAIR));
+    try!(steer.bind(&endpoint));
+    let control = try!(ctx.socket(PAIR));
+    try!(control.connect(&endpoint));
+
+    let thread = thread::spawn(move || {
+        let mut frontend = frontend;
+        let mut backend = backend;
+        let mut steer = steer;
+        match capture {
+            Some(mut capture) => proxy_steerable_with_capture(&mut frontend, &mut backend,
+                                                              &mut capture, &mut steer),
+            None => proxy_steerable(&mut frontend, &mut backend, &mut steer),
+        }
+    });
+    Ok(ProxyControl {
+        control: control,
+        thread: Some(thread),
+    })
+}
+
+impl ProxyControl {
+    /// Suspend the proxy's activity.
+    ///
+    /// With libzmq 4.2 or newer, this waits until the proxy has
+    /// processed the command, so no message is forwarded after it
+    /// returns. With older versions, it returns once the command has
+    /// been sent.
+    pub fn pause(&self) -> Result<()> {
+        self.command("PAUSE")
+    }
+
+    /// Resume the proxy's activity after `pause()`.
+    ///
+    /// Like `pause()`, this waits for the proxy to process the command
+    /// with libzmq 4.2 or newer.
+    pub fn resume(&self) -> Result<()> {
+        self.command("RESUME")
+    }
+
+    // Send a command and wait for the proxy to process it. libzmq
+    // (before 4.3.5) only replies to `STATISTICS`, so that is sent
+    // right after the command; as commands are processed in order,
+    // receiving its reply acknowledges the first one.
+    fn command(&self, command: &str) -> Result<()> {
+        try!(self.control.send(command, 0));
+        #[cfg(ZMQ_VERSION_4_2 = "1")]
+        try!(self.statistics());
+        Ok(())
+    }
+
+    /// Query the proxy's message and byte counters.
+    ///
+    /// This requires libzmq 4.2 or newer.
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    pub fn statistics(&self) -> Result<ProxyStatistics> {
+        try!(self.control.send("STATISTICS", 0));
+        let mut counters = [0u64; 8];
+        for (i, counter) in counters.iter_mut().enumerate() {
+            let msg = try!(self.control.recv_msg(0));
+            if msg.len() != 8 || msg.get_more() != (i < 7) {
+                return Err(Error::EPROTO);
+            }
+            *counter = unsafe { ptr::read_unaligned(msg.as_ptr() as *const u64) };
+        }
+        Ok(ProxyStatistics {
+            frontend_messages_received: counters[0],
+            frontend_bytes_received: counters[1],
+            frontend_messages_sent: counters[2],
+            frontend_bytes_sent: counters[3],
+            backend_messages_received: counters[4],
+            backend_bytes_received: counters[5],
+            backend_messages_sent: counters[6],
+            backend_bytes_sent: counters[7],
+        })
+    }
+
+    /// Terminate the proxy, and wait for its thread to finish.
+    ///
+    /// Returns the result of `zmq_proxy_steerable` as obtained by the
+    /// proxy thread.
+    pub fn terminate(mut self) -> Result<()> {
+        match self.terminate_and_join() {
+            Some(Ok(result)) => result,
+            Some(Err(e)) => panic::resume_unwind(e),
+            None => Ok(()),
+        }
+    }
+
+    fn terminate_and_join(&mut self) -> Option<thread::Result<Result<()>>> {
+        self.thread.take().map(|thread| {
+            // If sending fails, the proxy has already returned (e.g.
+            // due to the context being shut down), and joining won't
+            // block.
+            let _ = self.control.send("TERMINATE", 0);
+            thread.join()
+        })
+    }
+}
+
+impl Drop for ProxyControl {
+    fn drop(&mut self) {
+        if let Some(Ok(Err(e))) = self.terminate_and_join() {
+            debug!("proxy terminated with error: {}", e);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Integration with mio, enabled by the `mio` feature.
+
+use std::io;
+use std::os::unix::io::RawFd;
+
+use mio::event::Source;
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token};
+
+use super::{PollEvents, Result, Socket};
+
+/// A socket that can be registered with a `mio::Poll`.
+///
+/// The socket's `ZMQ_FD` descriptor is always registered for
+/// readability, regardless of the requested interest, since libzmq
+/// signals both incoming messages and room for outgoing ones that
+/// way. The descriptor is edge-triggered, so whenever it is reported
+/// as readable, use `drain_ready()` to handle all pending events
+/// before polling again.
+pub struct ReadinessSource {
+    socket: Socket,
+}
+
+impl ReadinessSource {
+    /// Wrap a socket for registration with mio.
+    pub fn new(socket: Socket) -> ReadinessSource {
+        ReadinessSource { socket: socket }
+    }
+
+    /// Return a reference to the underlying socket.
+    pub fn get_ref(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Return the underlying socket. It should be deregistered first.
+    pub fn into_inner(self) -> Socket {
+        self.socket
+    }
+
+    /// Handle pending events; see `Socket::drain_ready()`.
+    pub fn drain_ready<F>(&self, interest: PollEvents, handler: F) -> Result<()>
+        where F: FnMut(PollEvents) -> Result<()>
+    {
+        self.socket.drain_ready(interest, handler)
+    }
+
+    fn fd(&self) -> io::Result<RawFd> {
+        self.socket.get_fd().map_err(io::Error::from)
+    }
+}
+
+impl Source for ReadinessSource {
+    fn register(&mut self, registry: &Registry, token: Token, _: Interest) -> io::Result<()> {
+        let fd = try!(self.fd());
+        SourceFd(&fd).register(registry, token, Interest::READABLE)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, _: Interest) -> io::Result<()> {
+        let fd = try!(self.fd());
+        SourceFd(&fd).reregister(registry, token, Interest::READABLE)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        let fd = try!(self.fd());
+        SourceFd(&fd).deregister(registry)
+    }
+}
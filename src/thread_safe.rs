@@ -0,0 +1,182 @@
+//! Thread-safe sockets, as provided by the draft API.
+
+use std::result;
+use std::sync::Mutex;
+
+use super::{AsEndpoint, Context, ContextError, Endpoint, Error, Message, Result, Sendable, Socket, SocketType};
+
+/// A socket that can be shared between threads.
+///
+/// Only the draft socket types `SERVER`, `CLIENT`, `RADIO`, `DISH`,
+/// `SCATTER` and `GATHER` are thread-safe in libzmq. Unlike `Socket`,
+/// a `ThreadSafeSocket` is `Sync`, so it can be used from several
+/// threads at once, e.g. by wrapping it in an `Arc`.
+///
+/// These socket types do not support multipart messages, so only
+/// single-part operations are provided.
+pub struct ThreadSafeSocket {
+    socket: Socket,
+    // Held while binding or connecting, as both set `ZMQ_LAST_ENDPOINT`,
+    // which `Socket::bind()` reads back to record the bound endpoint.
+    last_endpoint: Mutex<()>,
+}
+
+// libzmq serializes all operations on thread-safe socket types
+// internally, and `Socket` guards its own state with mutexes.
+unsafe impl Sync for ThreadSafeSocket {}
+
+fn is_thread_safe(socket_type: SocketType) -> bool {
+    match socket_type {
+        SocketType::SERVER | SocketType::CLIENT |
+        SocketType::RADIO | SocketType::DISH |
+        SocketType::SCATTER | SocketType::GATHER => true,
+        _ => false,
+    }
+}
+
+impl ThreadSafeSocket {
+    /// Create a thread-safe socket of the given type.
+    ///
+    /// Returns `Err(Error::EINVAL)` if the socket type is not
+    /// thread-safe.
+    pub fn new(ctx: &Context, socket_type: SocketType) -> Result<ThreadSafeSocket> {
+        if !is_thread_safe(socket_type) {
+            return Err(Error::EINVAL);
+        }
+        let socket = try!(ctx.socket(socket_type));
+        Ok(ThreadSafeSocket::wrap(socket))
+    }
+
+    /// Wrap an existing socket.
+    ///
+    /// If the socket is not of a thread-safe type, it is handed back
+    /// as the error value.
+    pub fn from_socket(socket: Socket) -> result::Result<ThreadSafeSocket, Socket> {
+        match socket.get_socket_type() {
+            Ok(socket_type) if is_thread_safe(socket_type) => Ok(ThreadSafeSocket::wrap(socket)),
+            _ => Err(socket),
+        }
+    }
+
+    fn wrap(socket: Socket) -> ThreadSafeSocket {
+        ThreadSafeSocket {
+            socket: socket,
+            last_endpoint: Mutex::new(()),
+        }
+    }
+
+    /// Return the wrapped socket.
+    pub fn into_inner(self) -> Socket {
+        self.socket
+    }
+
+    /// Accept connections on a socket.
+    pub fn bind<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        let _guard = self.last_endpoint.lock().unwrap();
+        self.socket.bind(endpoint)
+    }
+
+    /// Stop accepting connections on a previously bound endpoint.
+    pub fn unbind<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        self.socket.unbind(endpoint)
+    }
+
+    /// Connect a socket.
+    pub fn connect<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        let _guard = self.last_endpoint.lock().unwrap();
+        self.socket.connect(endpoint)
+    }
+
+    /// Disconnect a previously connected socket.
+    pub fn disconnect<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        self.socket.disconnect(endpoint)
+    }
+
+    /// Return the endpoints this socket is currently bound to.
+    pub fn bound_endpoints(&self) -> Vec<String> {
+        self.socket.bound_endpoints()
+    }
+
+    /// Return the endpoints this socket is currently connected to.
+    pub fn connected_endpoints(&self) -> Vec<String> {
+        self.socket.connected_endpoints()
+    }
+
+    /// Join a group; only valid for `DISH` sockets.
+    pub fn join(&self, group: &str) -> Result<()> {
+        self.socket.join(group)
+    }
+
+    /// Leave a group previously joined with `join()`.
+    pub fn leave(&self, group: &str) -> Result<()> {
+        self.socket.leave(group)
+    }
+
+    /// Send a message.
+    ///
+    /// The only valid flag is `DONTWAIT`; passing `SNDMORE` results
+    /// in `Error::EINVAL`.
+    pub fn send<T>(&self, data: T, flags: i32) -> Result<()>
+        where T: Sendable
+    {
+        self.socket.send(data, flags)
+    }
+
+    /// Receive a message into a `Message`.
+    pub fn recv(&self, msg: &mut Message, flags: i32) -> Result<()> {
+        self.socket.recv(msg, flags)
+    }
+
+    /// Receive a message into a fresh `Message`.
+    pub fn recv_msg(&self, flags: i32) -> Result<Message> {
+        self.socket.recv_msg(flags)
+    }
+
+    /// Receive a message as a byte vector.
+    pub fn recv_bytes(&self, flags: i32) -> Result<Vec<u8>> {
+        self.socket.recv_bytes(flags)
+    }
+
+    /// Receive a `String`.
+    ///
+    /// If the received message is not valid UTF-8, it is returned as
+    /// the original `Vec` in the `Err` part of the inner result.
+    pub fn recv_string(&self, flags: i32) -> Result<result::Result<String, Vec<u8>>> {
+        self.socket.recv_string(flags)
+    }
+
+    /// Return the type of this socket.
+    pub fn get_socket_type(&self) -> Result<SocketType> {
+        self.socket.get_socket_type()
+    }
+
+    /// Return the address of the last endpoint this socket was bound to.
+    pub fn get_last_endpoint(&self) -> Result<result::Result<Endpoint, Vec<u8>>> {
+        let _guard = self.last_endpoint.lock().unwrap();
+        self.socket.get_last_endpoint()
+    }
+
+    pub fn get_linger(&self) -> Result<i32> {
+        self.socket.get_linger()
+    }
+
+    pub fn set_linger(&self, value: i32) -> Result<()> {
+        self.socket.set_linger(value)
+    }
+
+    pub fn get_rcvtimeo(&self) -> Result<i32> {
+        self.socket.get_rcvtimeo()
+    }
+
+    pub fn set_rcvtimeo(&self, value: i32) -> Result<()> {
+        self.socket.set_rcvtimeo(value)
+    }
+
+    pub fn get_sndtimeo(&self) -> Result<i32> {
+        self.socket.get_sndtimeo()
+    }
+
+    pub fn set_sndtimeo(&self, value: i32) -> Result<()> {
+        self.socket.set_sndtimeo(value)
+    }
+}
@@ -0,0 +1,167 @@
+//! Timers to be driven alongside `poll()`, modeled after `zmq_timers`.
+
+use std::time::{Duration, Instant};
+
+use super::{Error, Result};
+
+struct Timer<'a> {
+    id: usize,
+    interval: Duration,
+    when: Instant,
+    handler: Box<FnMut(usize) + 'a>,
+}
+
+/// A set of repeating timers.
+///
+/// `Timers` does not run in the background; instead, `timeout()` tells
+/// how long to wait (e.g. in `poll()` or `Poller::wait()`) until the
+/// next timer expires, and `execute()` invokes the handlers of all
+/// expired timers. A typical event loop looks like this:
+///
+/// ```no_run
+/// # let ctx = zmq::Context::new();
+/// # let socket = ctx.socket(zmq::DEALER).unwrap();
+/// let mut timers = zmq::Timers::new();
+/// timers.add(std::time::Duration::from_secs(1), |_| println!("heartbeat"));
+/// loop {
+///     let mut items = [socket.as_poll_item(zmq::POLLIN)];
+///     zmq::poll(&mut items, timers.timeout()).unwrap();
+///     if items[0].is_readable() {
+///         // handle incoming messages
+///     }
+///     timers.execute();
+/// }
+/// ```
+pub struct Timers<'a> {
+    timers: Vec<Timer<'a>>,
+    next_id: usize,
+}
+
+impl<'a> Timers<'a> {
+    /// Create an empty set of timers.
+    pub fn new() -> Timers<'a> {
+        Timers {
+            timers: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Add a timer, which invokes `handler` every `interval`.
+    ///
+    /// The handler is passed the id of the timer, which is also
+    /// returned by this function.
+    pub fn add<F>(&mut self, interval: Duration, handler: F) -> usize
+        where F: FnMut(usize) + 'a
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id: id,
+            interval: interval,
+            when: Instant::now() + interval,
+            handler: Box::new(handler),
+        });
+        id
+    }
+
+    /// Cancel a timer.
+    ///
+    /// Returns `Err(Error::EINVAL)` if there is no timer with the
+    /// given id.
+    pub fn cancel(&mut self, id: usize) -> Result<()> {
+        let index = try!(self.position(id));
+        self.timers.remove(index);
+        Ok(())
+    }
+
+    /// Change the interval of a timer, and restart it.
+    ///
+    /// Returns `Err(Error::EINVAL)` if there is no timer with the
+    /// given id.
+    pub fn set_interval(&mut self, id: usize, interval: Duration) -> Result<()> {
+        let index = try!(self.position(id));
+        let timer = &mut self.timers[index];
+        timer.interval = interval;
+        timer.when = Instant::now() + interval;
+        Ok(())
+    }
+
+    /// Restart a timer, so it next expires a full interval from now.
+    ///
+    /// Returns `Err(Error::EINVAL)` if there is no timer with the
+    /// given id.
+    pub fn reset(&mut self, id: usize) -> Result<()> {
+        let index = try!(self.position(id));
+        let timer = &mut self.timers[index];
+        timer.when = Instant::now() + timer.interval;
+        Ok(())
+    }
+
+    /// Return the time until the next timer expires, in milliseconds.
+    ///
+    /// The result is rounded up, and can be passed to `poll()` as the
+    /// timeout. If a timer has already expired, `0` is returned; if
+    /// there are no timers, `-1` is returned.
+    pub fn timeout(&self) -> i64 {
+        let now = Instant::now();
+        match self.timers.iter().map(|t| t.when).min() {
+            None => -1,
+            Some(when) if when <= now => 0,
+            Some(when) => {
+                let remaining = when - now;
+                let millis = remaining.as_secs() * 1000 +
+                    (remaining.subsec_nanos() as u64 + 999_999) / 1_000_000;
+                millis as i64
+            }
+        }
+    }
+
+    /// Return the time until the next timer expires.
+    ///
+    /// This is the `Duration` equivalent of `timeout()`, suitable for
+    /// `poll_duration()`: `None` is returned if there are no timers.
+    pub fn timeout_duration(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.timers.iter().map(|t| t.when).min().map(|when| {
+            if when <= now {
+                Duration::from_secs(0)
+            } else {
+                when - now
+            }
+        })
+    }
+
+    /// Invoke the handlers of all expired timers, and restart them.
+    ///
+    /// Returns the number of handlers invoked.
+    pub fn execute(&mut self) -> usize {
+        let now = Instant::now();
+        let mut count = 0;
+        for timer in self.timers.iter_mut().filter(|t| t.when <= now) {
+            timer.when = now + timer.interval;
+            (timer.handler)(timer.id);
+            count += 1;
+        }
+        count
+    }
+
+    /// Return the number of timers.
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Return true if there are no timers.
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    fn position(&self, id: usize) -> Result<usize> {
+        self.timers.iter().position(|t| t.id == id).ok_or(Error::EINVAL)
+    }
+}
+
+impl<'a> Default for Timers<'a> {
+    fn default() -> Self {
+        Timers::new()
+    }
+}
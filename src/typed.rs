@@ -0,0 +1,385 @@
+//! A typed socket layer, exposing only the operations valid for each
+//! socket type.
+
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::result;
+
+use super::{AsEndpoint, Context, ContextError, Endpoint, Error, Message, PollEvents, PollItem,
+            Result, Sendable, Socket, SocketOptionError, SocketOptions, SocketType};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A socket type usable with `TypedSocket`.
+///
+/// This trait is implemented by the marker types `Pair`, `Pub`,
+/// `Sub`, `Req`, `Rep`, `Dealer`, `Router`, `Pull`, `Push`, `XPub`,
+/// `XSub` and `Stream`, and cannot be implemented outside of this
+/// crate.
+pub trait SocketKind: private::Sealed {
+    /// The socket type created for this kind.
+    fn socket_type() -> SocketType;
+}
+
+/// A socket kind which can send messages at any time.
+pub trait CanSend: SocketKind {}
+
+/// A socket kind which can receive messages at any time.
+pub trait CanRecv: SocketKind {}
+
+macro_rules! socket_kinds {
+    ( $( $(#[$meta:meta])* $kind:ident => $socket_type:ident [$($cap:ident),*], )* ) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug)]
+            pub enum $kind {}
+
+            impl private::Sealed for $kind {}
+
+            impl SocketKind for $kind {
+                fn socket_type() -> SocketType {
+                    SocketType::$socket_type
+                }
+            }
+
+            $( impl $cap for $kind {} )*
+        )*
+    };
+}
+
+socket_kinds! {
+    /// Marker for `PAIR` sockets.
+    Pair => PAIR [CanSend, CanRecv],
+    /// Marker for `PUB` sockets.
+    Pub => PUB [CanSend],
+    /// Marker for `SUB` sockets.
+    Sub => SUB [CanRecv],
+    /// Marker for `REQ` sockets that are ready to send a request.
+    Req => REQ [],
+    /// Marker for `REP` sockets that are waiting for a request.
+    Rep => REP [],
+    /// Marker for `DEALER` sockets.
+    Dealer => DEALER [CanSend, CanRecv],
+    /// Marker for `ROUTER` sockets.
+    Router => ROUTER [CanSend, CanRecv],
+    /// Marker for `PULL` sockets.
+    Pull => PULL [CanRecv],
+    /// Marker for `PUSH` sockets.
+    Push => PUSH [CanSend],
+    /// Marker for `XPUB` sockets.
+    XPub => XPUB [CanSend, CanRecv],
+    /// Marker for `XSUB` sockets.
+    XSub => XSUB [CanSend, CanRecv],
+    /// Marker for `STREAM` sockets.
+    Stream => STREAM [CanSend, CanRecv],
+}
+
+/// Marker for `REQ` sockets that have sent a request, and are waiting
+/// for the reply.
+#[derive(Debug)]
+pub enum ReqAwaiting {}
+
+/// Marker for `REP` sockets that have received a request, and must
+/// send a reply.
+#[derive(Debug)]
+pub enum RepReplying {}
+
+/// A socket whose type is tracked at compile time.
+///
+/// A `TypedSocket` only provides the operations that are valid for
+/// its kind: e.g. `TypedSocket<Sub>` can subscribe and receive, but
+/// not send. The strict send/receive alternation of `REQ` and `REP`
+/// sockets is modeled by consuming the socket and returning it in its
+/// next state, e.g. `TypedSocket<Req>::send()` returns a
+/// `TypedSocket<ReqAwaiting>`, so that `Error::EFSM` cannot occur.
+///
+/// Use `into_inner()` to get at the underlying `Socket` for
+/// operations not covered here.
+pub struct TypedSocket<K> {
+    socket: Socket,
+    kind: PhantomData<K>,
+}
+
+/// A failed state transition of a `REQ` or `REP` socket.
+///
+/// The socket is handed back in the state it was in before the failed
+/// operation, so it can be retried (e.g. after `Error::EAGAIN`).
+pub struct TransitionError<S> {
+    pub socket: S,
+    pub error: Error,
+}
+
+impl<S> fmt::Debug for TransitionError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TransitionError({:?})", self.error)
+    }
+}
+
+impl<S> fmt::Display for TransitionError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl<S> error::Error for TransitionError<S> {
+    fn description(&self) -> &str {
+        "socket state transition failed"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        Some(&self.error)
+    }
+}
+
+impl<S> From<TransitionError<S>> for Error {
+    fn from(err: TransitionError<S>) -> Self {
+        err.error
+    }
+}
+
+/// The result of a state transition of a typed socket.
+pub type TransitionResult<T, S> = result::Result<T, TransitionError<TypedSocket<S>>>;
+
+impl<K: SocketKind> TypedSocket<K> {
+    /// Create a typed socket in the given context.
+    pub fn new(ctx: &Context) -> Result<TypedSocket<K>> {
+        let socket = try!(ctx.socket(K::socket_type()));
+        Ok(TypedSocket::wrap(socket))
+    }
+}
+
+impl<K> TypedSocket<K> {
+    fn wrap(socket: Socket) -> TypedSocket<K> {
+        TypedSocket {
+            socket: socket,
+            kind: PhantomData,
+        }
+    }
+
+    fn transition<N>(self) -> TypedSocket<N> {
+        TypedSocket::wrap(self.socket)
+    }
+
+    fn fail(self, error: Error) -> TransitionError<TypedSocket<K>> {
+        TransitionError {
+            socket: self,
+            error: error,
+        }
+    }
+
+    /// Return the underlying socket, giving up the type tracking.
+    pub fn into_inner(self) -> Socket {
+        self.socket
+    }
+
+    /// Accept connections on a socket.
+    pub fn bind<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        self.socket.bind(endpoint)
+    }
+
+    /// Stop accepting connections on a previously bound endpoint.
+    pub fn unbind<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        self.socket.unbind(endpoint)
+    }
+
+    /// Connect a socket.
+    pub fn connect<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        self.socket.connect(endpoint)
+    }
+
+    /// Disconnect a previously connected socket.
+    pub fn disconnect<E: ?Sized + AsEndpoint>(&self, endpoint: &E) -> result::Result<(), ContextError> {
+        self.socket.disconnect(endpoint)
+    }
+
+    /// Return the address of the last endpoint this socket was bound to.
+    pub fn get_last_endpoint(&self) -> Result<result::Result<Endpoint, Vec<u8>>> {
+        self.socket.get_last_endpoint()
+    }
+
+    /// Set all options present in `options`; see `Socket::apply()`.
+    pub fn apply(&self, options: &SocketOptions) -> result::Result<(), SocketOptionError> {
+        self.socket.apply(options)
+    }
+
+    /// Read the socket's options; see `Socket::options()`.
+    pub fn options(&self) -> result::Result<SocketOptions, SocketOptionError> {
+        self.socket.options()
+    }
+
+    /// Create a `PollItem` from the socket.
+    pub fn as_poll_item(&self, events: PollEvents) -> PollItem {
+        self.socket.as_poll_item(events)
+    }
+}
+
+impl<K: CanSend> TypedSocket<K> {
+    /// Send a message; see `Socket::send()`.
+    pub fn send<T>(&self, data: T, flags: i32) -> Result<()>
+        where T: Sendable
+    {
+        self.socket.send(data, flags)
+    }
+
+    /// Send a multipart message; see `Socket::send_multipart()`.
+    pub fn send_multipart<I, T>(&self, iter: I, flags: i32) -> Result<()>
+        where I: IntoIterator<Item=T>,
+              T: Into<Message>
+    {
+        self.socket.send_multipart(iter, flags)
+    }
+}
+
+impl<K: CanRecv> TypedSocket<K> {
+    /// Receive a message into a `Message`; see `Socket::recv()`.
+    pub fn recv(&self, msg: &mut Message, flags: i32) -> Result<()> {
+        self.socket.recv(msg, flags)
+    }
+
+    /// Receive a message into a fresh `Message`.
+    pub fn recv_msg(&self, flags: i32) -> Result<Message> {
+        self.socket.recv_msg(flags)
+    }
+
+    /// Receive a message as a byte vector.
+    pub fn recv_bytes(&self, flags: i32) -> Result<Vec<u8>> {
+        self.socket.recv_bytes(flags)
+    }
+
+    /// Receive a `String`; see `Socket::recv_string()`.
+    pub fn recv_string(&self, flags: i32) -> Result<result::Result<String, Vec<u8>>> {
+        self.socket.recv_string(flags)
+    }
+
+    /// Receive a multipart message.
+    pub fn recv_multipart(&self, flags: i32) -> Result<Vec<Vec<u8>>> {
+        self.socket.recv_multipart(flags)
+    }
+
+    /// Return true if there are more frames of a multipart message to
+    /// receive.
+    pub fn get_rcvmore(&self) -> Result<bool> {
+        self.socket.get_rcvmore()
+    }
+}
+
+impl TypedSocket<Sub> {
+    pub fn set_subscribe(&self, value: &[u8]) -> Result<()> {
+        self.socket.set_subscribe(value)
+    }
+
+    pub fn set_unsubscribe(&self, value: &[u8]) -> Result<()> {
+        self.socket.set_unsubscribe(value)
+    }
+}
+
+impl TypedSocket<XPub> {
+    pub fn set_xpub_verbose(&self, value: bool) -> Result<()> {
+        self.socket.set_xpub_verbose(value)
+    }
+}
+
+impl TypedSocket<Router> {
+    pub fn set_router_mandatory(&self, value: bool) -> Result<()> {
+        self.socket.set_router_mandatory(value)
+    }
+
+    pub fn set_probe_router(&self, value: bool) -> Result<()> {
+        self.socket.set_probe_router(value)
+    }
+}
+
+impl TypedSocket<Dealer> {
+    pub fn set_probe_router(&self, value: bool) -> Result<()> {
+        self.socket.set_probe_router(value)
+    }
+}
+
+impl TypedSocket<Req> {
+    /// Send a request, after which the socket awaits the reply.
+    pub fn send<T>(self, data: T, flags: i32) -> TransitionResult<TypedSocket<ReqAwaiting>, Req>
+        where T: Sendable
+    {
+        match self.socket.send(data, flags) {
+            Ok(()) => Ok(self.transition()),
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+
+    /// Send a multipart request, after which the socket awaits the
+    /// reply.
+    ///
+    /// If sending fails after some parts have already been sent, the
+    /// returned socket is in an inconsistent state.
+    pub fn send_multipart<I, T>(self, iter: I, flags: i32)
+                                -> TransitionResult<TypedSocket<ReqAwaiting>, Req>
+        where I: IntoIterator<Item=T>,
+              T: Into<Message>
+    {
+        match self.socket.send_multipart(iter, flags) {
+            Ok(()) => Ok(self.transition()),
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+}
+
+impl TypedSocket<ReqAwaiting> {
+    /// Receive the reply, after which the socket is ready to send the
+    /// next request.
+    ///
+    /// The reply is received as a whole, as all its parts must be
+    /// received before sending again.
+    pub fn recv_multipart(self, flags: i32)
+                          -> TransitionResult<(TypedSocket<Req>, Vec<Vec<u8>>), ReqAwaiting> {
+        match self.socket.recv_multipart(flags) {
+            Ok(parts) => Ok((self.transition(), parts)),
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+}
+
+impl TypedSocket<Rep> {
+    /// Receive a request, after which the socket must send a reply.
+    ///
+    /// The request is received as a whole, as all its parts must be
+    /// received before replying.
+    pub fn recv_multipart(self, flags: i32)
+                          -> TransitionResult<(TypedSocket<RepReplying>, Vec<Vec<u8>>), Rep> {
+        match self.socket.recv_multipart(flags) {
+            Ok(parts) => Ok((self.transition(), parts)),
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+}
+
+impl TypedSocket<RepReplying> {
+    /// Send the reply, after which the socket waits for the next
+    /// request.
+    pub fn send<T>(self, data: T, flags: i32) -> TransitionResult<TypedSocket<Rep>, RepReplying>
+        where T: Sendable
+    {
+        match self.socket.send(data, flags) {
+            Ok(()) => Ok(self.transition()),
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+
+    /// Send a multipart reply, after which the socket waits for the
+    /// next request.
+    ///
+    /// If sending fails after some parts have already been sent, the
+    /// returned socket is in an inconsistent state.
+    pub fn send_multipart<I, T>(self, iter: I, flags: i32)
+                                -> TransitionResult<TypedSocket<Rep>, RepReplying>
+        where I: IntoIterator<Item=T>,
+              T: Into<Message>
+    {
+        match self.socket.send_multipart(iter, flags) {
+            Ok(()) => Ok(self.transition()),
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+}
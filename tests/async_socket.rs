@@ -0,0 +1,248 @@
+#![cfg(all(feature = "async", unix))]
+
+extern crate futures;
+extern crate tokio;
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::ptr;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread;
+use std::time::Duration;
+
+use futures::future;
+use futures::{Sink, SinkExt, StreamExt};
+use tokio::runtime::{Builder, Runtime};
+use tokio::time::timeout;
+use zmq::{AsyncSocket, Multipart};
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().enable_io().enable_time().build().unwrap()
+}
+
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+}
+
+test!(test_async_send_recv, {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.bind("inproc://async-send-recv").unwrap();
+    let mut pull = AsyncSocket::new(pull).unwrap();
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://async-send-recv").unwrap();
+    let mut push = AsyncSocket::new(push).unwrap();
+
+    rt.block_on(push.send("hello", 0)).unwrap();
+    let msg = rt.block_on(pull.recv_msg(0)).unwrap();
+    assert_eq!(msg.as_str(), Some("hello"));
+
+    rt.block_on(push.send_multipart(vec!["a", "b", "c"], 0)).unwrap();
+    assert_eq!(rt.block_on(pull.recv_multipart(0)).unwrap(),
+               vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+});
+
+test!(test_async_recv_waits, {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.bind("inproc://async-recv-waits").unwrap();
+    let mut pull = AsyncSocket::new(pull).unwrap();
+
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://async-recv-waits").unwrap();
+    let sender = thread::spawn(move || {
+        for i in 0..10 {
+            thread::sleep(Duration::from_millis(10));
+            push.send(&format!("{}", i), 0).unwrap();
+        }
+    });
+    for i in 0..10 {
+        let msg = rt.block_on(pull.recv_msg(0)).unwrap();
+        assert_eq!(msg.as_str(), Some(&format!("{}", i)[..]));
+    }
+    sender.join().unwrap();
+});
+
+test!(test_async_send_waits, {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.set_sndhwm(1).unwrap();
+    push.bind("inproc://async-send-waits").unwrap();
+    let mut push = AsyncSocket::new(push).unwrap();
+
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.set_rcvhwm(1).unwrap();
+    pull.set_rcvtimeo(5000).unwrap();
+    pull.connect("inproc://async-send-waits").unwrap();
+    let receiver = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        (0..20).map(|_| pull.recv_multipart(0).unwrap()).count()
+    });
+    for _ in 0..20 {
+        let send = push.send_multipart(vec!["x", "y"], 0);
+        rt.block_on(timeout(Duration::from_secs(5), send)).expect("send timed out").unwrap();
+    }
+    assert_eq!(receiver.join().unwrap(), 20);
+});
+
+test!(test_async_recv_cancel, {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.bind("inproc://async-recv-cancel").unwrap();
+    let mut pull = AsyncSocket::new(pull).unwrap();
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://async-recv-cancel").unwrap();
+
+    // Dropping a pending receive does not lose the next message.
+    {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pull.recv_msg(0);
+        match Pin::new(&mut future).poll(&mut cx) {
+            Poll::Pending => {}
+            Poll::Ready(result) => panic!("unexpected result: {:?}", result),
+        }
+    }
+    push.send("after", 0).unwrap();
+    let msg = rt.block_on(pull.recv_msg(0)).unwrap();
+    assert_eq!(msg.as_str(), Some("after"));
+
+    let socket = pull.into_inner();
+    assert_eq!(socket.get_socket_type().unwrap(), zmq::PULL);
+});
+
+test!(test_async_stream, {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.bind("inproc://async-stream").unwrap();
+    let mut pull = AsyncSocket::new(pull).unwrap();
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://async-stream").unwrap();
+    for i in 0..6 {
+        push.send_multipart(vec![format!("{}", i), "payload".to_owned()], 0).unwrap();
+    }
+
+    let even = pull.by_ref()
+        .take(6)
+        .map(|parts| parts.unwrap())
+        .filter(|parts| future::ready(parts[0][0] % 2 == 0))
+        .map(|parts| String::from_utf8(parts[0].clone()).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rt.block_on(even), vec!["0", "2", "4"]);
+});
+
+test!(test_async_sink_forward, {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let publisher = ctx.socket(zmq::PUB).unwrap();
+    publisher.bind("inproc://async-forward-pub").unwrap();
+    let subscriber = ctx.socket(zmq::SUB).unwrap();
+    subscriber.set_subscribe(b"").unwrap();
+    subscriber.connect("inproc://async-forward-pub").unwrap();
+    let subscriber = AsyncSocket::new(subscriber).unwrap();
+
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.bind("inproc://async-forward-push").unwrap();
+    let mut push = AsyncSocket::new(push).unwrap();
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.connect("inproc://async-forward-push").unwrap();
+
+    // Subscriptions propagate asynchronously, so keep publishing
+    // until the first message gets through.
+    let publisher = thread::spawn(move || {
+        for _ in 0..100 {
+            publisher.send_multipart(vec!["topic", "data"], 0).unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+    rt.block_on(subscriber.take(3).forward(&mut push)).unwrap();
+    for _ in 0..3 {
+        assert_eq!(pull.recv_multipart(0).unwrap(), vec![b"topic".to_vec(), b"data".to_vec()]);
+    }
+
+    let message: Multipart = vec![b"direct".to_vec()];
+    rt.block_on(SinkExt::send(&mut push, message.clone())).unwrap();
+    assert_eq!(pull.recv_multipart(0).unwrap(), message);
+    publisher.join().unwrap();
+});
+
+test!(test_async_sink_start_send_pending, {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.bind("inproc://async-sink-pending").unwrap();
+    let mut push = AsyncSocket::new(push).unwrap();
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.connect("inproc://async-sink-pending").unwrap();
+
+    // Starting another send before the first one was flushed is
+    // rejected, rather than replacing the pending message.
+    Pin::new(&mut push).start_send(vec![b"first".to_vec()]).unwrap();
+    assert_eq!(Pin::new(&mut push).start_send(vec![b"second".to_vec()]),
+               Err(zmq::Error::EFSM));
+    rt.block_on(SinkExt::flush(&mut push)).unwrap();
+    assert_eq!(pull.recv_multipart(0).unwrap(), vec![b"first".to_vec()]);
+});
+
+// Return a connected pair of sockets, leaked so that futures borrowing
+// them are `'static`, as required for spawning.
+fn leaked_pair(ctx: &zmq::Context, endpoint: &str)
+               -> (&'static mut AsyncSocket, &'static mut AsyncSocket) {
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.bind(endpoint).unwrap();
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect(endpoint).unwrap();
+    (Box::leak(Box::new(AsyncSocket::new(push).unwrap())),
+     Box::leak(Box::new(AsyncSocket::new(pull).unwrap())))
+}
+
+test!(test_async_spawn_multi_thread, {
+    let rt = Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_io()
+        .enable_time()
+        .build()
+        .unwrap();
+    let _guard = rt.enter();
+    let ctx = zmq::Context::new();
+
+    let (push, pull) = leaked_pair(&ctx, "inproc://async-spawn");
+    let recv = rt.spawn(pull.recv_msg(0));
+    let send = rt.spawn(push.send("hello", 0));
+    rt.block_on(send).unwrap().unwrap();
+    assert_eq!(rt.block_on(recv).unwrap().unwrap().as_str(), Some("hello"));
+
+    let (push, pull) = leaked_pair(&ctx, "inproc://async-spawn-multipart");
+    let recv = rt.spawn(pull.recv_multipart(0));
+    let send = rt.spawn(push.send_multipart(vec!["a", "b"], 0));
+    rt.block_on(send).unwrap().unwrap();
+    assert_eq!(rt.block_on(recv).unwrap().unwrap(), vec![b"a".to_vec(), b"b".to_vec()]);
+});
@@ -0,0 +1,8 @@
+extern crate zmq;
+
+fn main() {
+    let ctx = zmq::Context::new();
+    let req = ctx.typed_socket::<zmq::Req>().unwrap();
+    let awaiting = req.send("first", 0).unwrap();
+    awaiting.send("second", 0); //~ ERROR `send` exists
+}
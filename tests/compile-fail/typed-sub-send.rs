@@ -0,0 +1,7 @@
+extern crate zmq;
+
+fn main() {
+    let ctx = zmq::Context::new();
+    let sub = ctx.typed_socket::<zmq::Sub>().unwrap();
+    sub.send("message", 0); //~ ERROR `send` exists
+}
@@ -78,7 +78,7 @@ pub fn with_connection<F, G>(address: &str,
 
     let push_socket = ctx.socket(parent_type).unwrap();
     push_socket.bind(address).unwrap();
-    let endpoint = push_socket.get_last_endpoint().unwrap().unwrap();
+    let endpoint = push_socket.get_last_endpoint().unwrap().unwrap().to_string();
 
     let thread = {
         let w_ctx = ctx.clone();
@@ -96,5 +96,5 @@ pub fn with_connection<F, G>(address: &str,
 fn connect_socket(ctx: &zmq::Context,
                   typ: zmq::SocketType,
                   address: &str) -> Result<zmq::Socket, zmq::Error> {
-    ctx.socket(typ).and_then(|socket| socket.connect(address).map(|_| socket))
+    ctx.socket(typ).and_then(|socket| socket.connect(address).map(|_| socket).map_err(zmq::Error::from))
 }
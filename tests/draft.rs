@@ -0,0 +1,137 @@
+#![cfg(ZMQ_HAS_DRAFT = "1")]
+
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+
+use zmq::{Context, Message, ThreadSafeSocket};
+
+test!(test_server_client_routing_id, {
+    let ctx = Context::new();
+    let server = ctx.socket(zmq::SERVER).unwrap();
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+
+    let client = ctx.socket(zmq::CLIENT).unwrap();
+    client.connect(&ep).unwrap();
+    client.send("ping", 0).unwrap();
+
+    let request = server.recv_msg(0).unwrap();
+    assert_eq!(&request[..], b"ping");
+    let routing_id = request.routing_id().expect("no routing id set");
+
+    let mut reply = Message::from("pong");
+    assert_eq!(reply.routing_id(), None);
+    reply.set_routing_id(routing_id).unwrap();
+    assert_eq!(reply.routing_id(), Some(routing_id));
+    server.send(reply, 0).unwrap();
+
+    assert_eq!(&client.recv_msg(0).unwrap()[..], b"pong");
+});
+
+test!(test_radio_dish_group, {
+    let ctx = Context::new();
+    let radio = ctx.socket(zmq::RADIO).unwrap();
+    radio.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = radio.get_last_endpoint().unwrap().unwrap();
+
+    let dish = ctx.socket(zmq::DISH).unwrap();
+    dish.join("news").unwrap();
+    dish.connect(&ep).unwrap();
+
+    // Joining propagates to the radio asynchronously, so keep
+    // publishing until the dish receives something.
+    let msg = loop {
+        let mut other = Message::from("weather");
+        other.set_group("other").unwrap();
+        radio.send(other, 0).unwrap();
+        let mut news = Message::from("headline");
+        news.set_group("news").unwrap();
+        assert_eq!(news.group(), Some("news"));
+        radio.send(news, 0).unwrap();
+
+        let mut items = [dish.as_poll_item(zmq::POLLIN)];
+        if zmq::poll(&mut items, 100).unwrap() > 0 {
+            break dish.recv_msg(0).unwrap();
+        }
+    };
+    assert_eq!(&msg[..], b"headline");
+    assert_eq!(msg.group(), Some("news"));
+
+    dish.leave("news").unwrap();
+    assert_eq!(dish.leave("news"), Err(zmq::Error::EINVAL));
+});
+
+test!(test_group_invalid, {
+    let mut msg = Message::new();
+    assert_eq!(msg.group(), None);
+    assert_eq!(msg.set_group("a\0b"), Err(zmq::Error::EINVAL));
+    assert_eq!(msg.set_group(&"x".repeat(256)), Err(zmq::Error::EINVAL));
+    #[cfg(ZMQ_VERSION_4_3 = "1")]
+    {
+        msg.set_group(&"x".repeat(255)).unwrap();
+        assert_eq!(msg.group().map(str::len), Some(255));
+    }
+    assert_eq!(msg.set_routing_id(0), Err(zmq::Error::EINVAL));
+});
+
+test!(test_thread_safe_socket_type, {
+    let ctx = Context::new();
+    assert!(ThreadSafeSocket::new(&ctx, zmq::REQ).err() == Some(zmq::Error::EINVAL));
+    let socket = ctx.socket(zmq::REP).unwrap();
+    let socket = ThreadSafeSocket::from_socket(socket).err().unwrap();
+    assert_eq!(socket.get_socket_type().unwrap(), zmq::REP);
+
+    let socket = ctx.socket(zmq::CLIENT).unwrap();
+    let socket = ThreadSafeSocket::from_socket(socket).ok().unwrap();
+    assert_eq!(socket.get_socket_type().unwrap(), zmq::CLIENT);
+});
+
+test!(test_thread_safe_socket_shared, {
+    let ctx = Context::new();
+    let server = ThreadSafeSocket::new(&ctx, zmq::SERVER).unwrap();
+    server.bind("inproc://thread-safe").unwrap();
+    let client = Arc::new(ThreadSafeSocket::new(&ctx, zmq::CLIENT).unwrap());
+    client.connect("inproc://thread-safe").unwrap();
+
+    let threads: Vec<_> = (0..4).map(|i| {
+        let client = client.clone();
+        thread::spawn(move || {
+            client.send(format!("{}", i).as_str(), 0).unwrap();
+        })
+    }).collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    let mut received: Vec<_> = (0..4).map(|_| {
+        server.recv_string(0).unwrap().unwrap()
+    }).collect();
+    received.sort();
+    assert_eq!(received, vec!["0", "1", "2", "3"]);
+});
+
+test!(test_thread_safe_socket_concurrent_bind, {
+    let ctx = Context::new();
+    let server = Arc::new(ThreadSafeSocket::new(&ctx, zmq::SERVER).unwrap());
+    let threads: Vec<_> = (0..4).map(|_| {
+        let server = server.clone();
+        thread::spawn(move || {
+            server.bind("tcp://127.0.0.1:*").unwrap();
+        })
+    }).collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    // Each bind records the endpoint it resolved to.
+    let mut bound = server.bound_endpoints();
+    bound.sort();
+    bound.dedup();
+    assert_eq!(bound.len(), 4);
+    assert!(bound.iter().all(|ep| !ep.ends_with(":*")));
+});
@@ -0,0 +1,103 @@
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use zmq::{Endpoint, IpcPath, ParseEndpointError, Port};
+
+#[test]
+fn test_endpoint_parse() {
+    let cases = vec![
+        ("tcp://127.0.0.1:5555",
+         Endpoint::Tcp { host: "127.0.0.1".to_owned(), port: Port::Number(5555), source: None }),
+        ("tcp://*:*",
+         Endpoint::Tcp { host: "*".to_owned(), port: Port::Wildcard, source: None }),
+        ("tcp://[::1]:5555",
+         Endpoint::Tcp { host: "[::1]".to_owned(), port: Port::Number(5555), source: None }),
+        ("tcp://eth0;10.0.0.1:80",
+         Endpoint::Tcp {
+             host: "10.0.0.1".to_owned(),
+             port: Port::Number(80),
+             source: Some("eth0".to_owned()),
+         }),
+        ("udp://239.0.0.1:5556",
+         Endpoint::Udp { host: "239.0.0.1".to_owned(), port: Port::Number(5556), source: None }),
+        ("ipc:///tmp/feeds/0", Endpoint::Ipc { path: IpcPath::Path("/tmp/feeds/0".to_owned()) }),
+        ("ipc://@abstract", Endpoint::Ipc { path: IpcPath::Abstract("abstract".to_owned()) }),
+        ("ipc://*", Endpoint::Ipc { path: IpcPath::Wildcard }),
+        ("inproc://my-endpoint", Endpoint::Inproc { name: "my-endpoint".to_owned() }),
+        ("pgm://eth0;239.192.1.1:5555",
+         Endpoint::Pgm {
+             interface: "eth0".to_owned(),
+             address: "239.192.1.1".to_owned(),
+             port: 5555,
+         }),
+        ("epgm://192.168.1.1;239.192.1.1:5555",
+         Endpoint::Epgm {
+             interface: "192.168.1.1".to_owned(),
+             address: "239.192.1.1".to_owned(),
+             port: 5555,
+         }),
+        ("tipc://{5560,0,0}", Endpoint::Tipc { address: "{5560,0,0}".to_owned() }),
+        ("vmci://1:5555", Endpoint::Vmci { address: "1".to_owned(), port: Port::Number(5555) }),
+    ];
+    for (s, endpoint) in cases {
+        assert_eq!(s.parse::<Endpoint>().unwrap(), endpoint);
+        assert_eq!(endpoint.to_string(), s);
+    }
+}
+
+#[test]
+fn test_endpoint_parse_errors() {
+    let cases = vec![
+        ("127.0.0.1:5555", ParseEndpointError::MissingTransport),
+        ("://foo", ParseEndpointError::MissingTransport),
+        ("foo://bar", ParseEndpointError::UnknownTransport("foo".to_owned())),
+        ("tcp://", ParseEndpointError::InvalidAddress),
+        ("tcp://127.0.0.1", ParseEndpointError::InvalidAddress),
+        ("tcp://:5555", ParseEndpointError::InvalidAddress),
+        ("tcp://[::1:5555", ParseEndpointError::InvalidAddress),
+        ("tcp://127.0.0.1:65536", ParseEndpointError::InvalidPort),
+        ("tcp://127.0.0.1:http", ParseEndpointError::InvalidPort),
+        ("pgm://239.192.1.1:5555", ParseEndpointError::InvalidAddress),
+        ("pgm://eth0;239.192.1.1:*", ParseEndpointError::InvalidPort),
+        ("ipc://@", ParseEndpointError::InvalidAddress),
+        ("inproc://a\0b", ParseEndpointError::NulByte),
+    ];
+    for (s, err) in cases {
+        assert_eq!(s.parse::<Endpoint>(), Err(err));
+    }
+    assert_eq!(zmq::Error::from(ParseEndpointError::InvalidPort), zmq::Error::EINVAL);
+}
+
+test!(test_endpoint_bind_connect, {
+    let ctx = zmq::Context::new();
+    let receiver = ctx.socket(zmq::PULL).unwrap();
+    let endpoint: Endpoint = "tcp://127.0.0.1:*".parse().unwrap();
+    receiver.bind(&endpoint).unwrap();
+
+    let bound = receiver.get_last_endpoint().unwrap().unwrap();
+    match bound {
+        Endpoint::Tcp { port: Port::Number(_), .. } => {}
+        ref ep => panic!("unexpected endpoint: {}", ep),
+    }
+    assert_eq!(receiver.bound_endpoints(), vec![bound.to_string()]);
+
+    let sender = ctx.socket(zmq::PUSH).unwrap();
+    sender.connect(&bound).unwrap();
+    sender.send("hello", 0).unwrap();
+    assert_eq!(receiver.recv_bytes(0).unwrap(), b"hello");
+
+    sender.disconnect(&bound).unwrap();
+    receiver.unbind(&bound).unwrap();
+});
+
+test!(test_endpoint_nul_byte, {
+    let ctx = zmq::Context::new();
+    let socket = ctx.socket(zmq::PAIR).unwrap();
+    assert_eq!(socket.bind("inproc://a\0b").unwrap_err().error, zmq::Error::EINVAL);
+    assert_eq!(socket.connect("inproc://a\0b").unwrap_err().error, zmq::Error::EINVAL);
+    assert_eq!(socket.unbind("inproc://a\0b").unwrap_err().error, zmq::Error::EINVAL);
+    assert_eq!(socket.disconnect("inproc://a\0b").unwrap_err().error, zmq::Error::EINVAL);
+    assert!(socket.bound_endpoints().is_empty());
+});
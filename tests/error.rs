@@ -9,3 +9,40 @@ fn from_raw_eintr() {
   let error = Error::from_raw(errno::EINTR);
   assert_eq!(error, Error::EINTR);
 }
+
+#[test]
+fn from_raw_unknown() {
+  let error = Error::from_raw(-12345);
+  assert_eq!(error, Error::Other(-12345));
+  assert_eq!(error.to_raw(), -12345);
+  // Formatting must not panic either.
+  let _ = error.to_string();
+}
+
+#[test]
+fn to_raw_roundtrip() {
+  for &error in &[Error::EAGAIN, Error::EINVAL, Error::ETERM, Error::EFSM, Error::EMTHREAD] {
+    assert_eq!(Error::from_raw(error.to_raw()), error);
+  }
+}
+
+#[test]
+fn classification() {
+  assert!(Error::EAGAIN.is_retryable());
+  assert!(Error::EINTR.is_retryable());
+  assert!(!Error::ETERM.is_retryable());
+  assert!(Error::ETERM.is_terminal());
+  assert!(!Error::EFSM.is_terminal());
+  assert!(Error::EFSM.is_state());
+  assert!(!Error::Other(-1).is_state());
+}
+
+#[test]
+fn with_context() {
+  let error = Error::ECONNREFUSED.with_context("connect", Some("tcp://host:5555"));
+  assert_eq!(error.to_string(), format!("connect tcp://host:5555: {}", Error::ECONNREFUSED));
+  assert_eq!(Error::from(error), Error::ECONNREFUSED);
+  let error = Error::ETERM.with_context("recv", None);
+  assert_eq!(error.to_string(), format!("recv: {}", Error::ETERM));
+  assert!(error.is_terminal());
+}
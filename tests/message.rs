@@ -7,6 +7,8 @@ mod common;
 
 use zmq::Message;
 use quickcheck::{Gen, Arbitrary};
+use std::borrow::Cow;
+use std::sync::Arc;
 
 // A pair which contains two non-equal values
 #[derive(Clone, Debug)]
@@ -33,4 +35,84 @@ quickcheck! {
         let original = Message::from_slice(&input.clone());
         Message::from(input) == original
     }
+
+    fn msg_box_roundtrip(input: Vec<u8>) -> bool {
+        let original = Message::from_slice(&input);
+        Message::from(input.into_boxed_slice()) == original
+    }
+
+    fn msg_arc_roundtrip(input: Vec<u8>) -> bool {
+        let original = Message::from_slice(&input);
+        let shared: Arc<[u8]> = input.into();
+        Message::from(shared) == original
+    }
+
+    fn msg_string_roundtrip(input: String) -> bool {
+        let original = Message::from_slice(input.as_bytes());
+        Message::from(input) == original
+    }
+
+    fn msg_cow_roundtrip(input: Vec<u8>) -> bool {
+        let original = Message::from_slice(&input);
+        Message::from(Cow::Borrowed(&input[..])) == original &&
+            Message::from(Cow::Owned::<[u8]>(input)) == original
+    }
+
+    fn msg_share_eq(input: Vec<u8>) -> bool {
+        let mut original = Message::from(input);
+        original.share().unwrap() == original
+    }
+
+    fn msg_owner_roundtrip(input: Vec<u8>) -> bool {
+        let original = Message::from_slice(&input);
+        Message::from_owner(input) == original
+    }
+
+    fn msg_try_from_slice(input: Vec<u8>) -> bool {
+        Message::try_from_slice(&input).unwrap() == Message::from_slice(&input)
+    }
+
+    fn msg_try_with_capacity(len: u16) -> bool {
+        let msg = Message::try_with_capacity(len as usize).unwrap();
+        msg.len() == len as usize && msg.iter().all(|&b| b == 0)
+    }
+}
+
+#[test]
+fn msg_owner_copy_on_write() {
+    let shared: Arc<[u8]> = vec![1, 2, 3].into();
+    let mut msg = Message::from(shared.clone());
+    msg[0] = 42;
+    assert_eq!(&msg[..], &[42, 2, 3]);
+    assert_eq!(&shared[..], &[1, 2, 3]);
+}
+
+#[test]
+fn msg_clone() {
+    let mut original = Message::from(vec![0u8; 1024]);
+    let mut copy = original.clone();
+    assert_eq!(copy, original);
+    assert!(!original.is_shared());
+    assert!(!copy.is_shared());
+
+    copy[0] = 1;
+    assert_eq!(original[0], 0);
+    original[1] = 2;
+    assert_eq!(copy[1], 0);
+}
+
+#[test]
+fn msg_share() {
+    let mut original = Message::from(vec![0u8; 1024]);
+    assert!(!original.is_shared());
+
+    let mut copy = original.share().unwrap();
+    assert!(original.is_shared());
+    assert!(copy.is_shared());
+
+    // Mutation of a shared message must not affect the other copies.
+    copy[0] = 1;
+    assert!(!copy.is_shared());
+    assert_eq!(original[0], 0);
+    assert_eq!(copy[0], 1);
 }
@@ -0,0 +1,66 @@
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use zmq::{Context, Monitor, SocketEvent};
+
+test!(test_monitor_events, {
+    let ctx = Context::new();
+    let server = ctx.socket(zmq::REP).unwrap();
+    server.monitor("inproc://monitor-server", zmq::EVENT_ALL).unwrap();
+    let monitor = Monitor::connect(&ctx, "inproc://monitor-server").unwrap();
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap().to_string();
+    match monitor.recv_event(0).unwrap() {
+        SocketEvent::Listening { ref endpoint, .. } => assert_eq!(endpoint, &ep),
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    let client = ctx.socket(zmq::REQ).unwrap();
+    client.connect(&ep).unwrap();
+    loop {
+        let event = monitor.recv_event(0).unwrap();
+        if event.kind() == zmq::EVENT_ACCEPTED {
+            assert_eq!(event.endpoint(), ep);
+            break;
+        }
+    }
+
+    server.stop_monitor().unwrap();
+    loop {
+        if let SocketEvent::MonitorStopped { .. } = monitor.recv_event(0).unwrap() {
+            break;
+        }
+    }
+});
+
+test!(test_monitor_poll, {
+    let ctx = Context::new();
+    let server = ctx.socket(zmq::PULL).unwrap();
+    server.monitor("inproc://monitor-poll", zmq::EVENT_LISTENING).unwrap();
+    let monitor = Monitor::connect(&ctx, "inproc://monitor-poll").unwrap();
+
+    assert_eq!(Err(zmq::Error::EAGAIN), monitor.recv_event(zmq::DONTWAIT));
+    assert_eq!(Err(zmq::Error::EINVAL), server.monitor("inproc://a\0b", zmq::EVENT_ALL));
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let mut items = [monitor.as_poll_item(zmq::POLLIN)];
+    assert_eq!(zmq::poll(&mut items, 1000).unwrap(), 1);
+    assert_eq!(monitor.recv_event(0).unwrap().kind(), zmq::EVENT_LISTENING);
+});
+
+#[test]
+fn test_event_from_raw() {
+    let event = SocketEvent::from_raw(0x0004, 100, "tcp://127.0.0.1:5555".to_owned()).unwrap();
+    assert_eq!(event, SocketEvent::ConnectRetried {
+        endpoint: "tcp://127.0.0.1:5555".to_owned(),
+        interval: 100,
+    });
+    assert_eq!(event.kind(), zmq::EVENT_CONNECT_RETRIED);
+    assert_eq!(event.endpoint(), "tcp://127.0.0.1:5555");
+
+    assert_eq!(SocketEvent::from_raw(0x8000, 0, String::new()), Err(zmq::Error::EPROTO));
+    assert_eq!(SocketEvent::decode(b"\x01\x00", b""), Err(zmq::Error::EPROTO));
+}
@@ -25,6 +25,19 @@ fn test_pipe_poll() {
     writer_thread.join().unwrap();
 }
 
+#[test]
+fn test_pipe_poller() {
+    let (pipe_read, pipe_write) = unistd::pipe().expect("pipe creation failed");
+    let writer_thread = thread::spawn(move || { pipe_writer(pipe_write); });
+    let mut poller = zmq::Poller::new().unwrap();
+    poller.add_fd(pipe_read, 42, zmq::POLLIN).unwrap();
+
+    let events: Vec<_> = poller.wait(1000).unwrap().collect();
+    assert_eq!(events, vec![(42, zmq::POLLIN)]);
+
+    writer_thread.join().unwrap();
+}
+
 fn pipe_writer(fd: RawFd) {
     unistd::write(fd, b"X").expect("pipe write failed");
 }
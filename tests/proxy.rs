@@ -0,0 +1,50 @@
+#![cfg(ZMQ_VERSION_4_2 = "1")]
+
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use zmq::Context;
+
+test!(test_steerable_proxy, {
+    let ctx = Context::new();
+    let frontend = ctx.socket(zmq::PULL).unwrap();
+    frontend.bind("inproc://proxy-frontend").unwrap();
+    let backend = ctx.socket(zmq::PUSH).unwrap();
+    backend.bind("inproc://proxy-backend").unwrap();
+
+    let sender = ctx.socket(zmq::PUSH).unwrap();
+    sender.connect("inproc://proxy-frontend").unwrap();
+    let receiver = ctx.socket(zmq::PULL).unwrap();
+    receiver.connect("inproc://proxy-backend").unwrap();
+
+    let control = zmq::spawn_proxy(&ctx, frontend, backend, None).unwrap();
+
+    sender.send("hello", 0).unwrap();
+    assert_eq!(receiver.recv_bytes(0).unwrap(), b"hello");
+
+    let stats = control.statistics().unwrap();
+    assert_eq!(stats.frontend_messages_received, 1);
+    assert_eq!(stats.frontend_bytes_received, 5);
+    assert_eq!(stats.backend_messages_sent, 1);
+    assert_eq!(stats.backend_bytes_sent, 5);
+
+    control.pause().unwrap();
+    sender.send("paused", 0).unwrap();
+    assert_eq!(receiver.poll(zmq::POLLIN, 100).unwrap(), 0);
+    control.resume().unwrap();
+    assert_eq!(receiver.recv_bytes(0).unwrap(), b"paused");
+
+    control.terminate().unwrap();
+});
+
+test!(test_steerable_proxy_drop, {
+    let ctx = Context::new();
+    let frontend = ctx.socket(zmq::XSUB).unwrap();
+    let backend = ctx.socket(zmq::XPUB).unwrap();
+    let control = zmq::spawn_proxy(&ctx, frontend, backend, None).unwrap();
+    // Dropping the handle must terminate the proxy, releasing its
+    // sockets, so that the context can be destroyed.
+    drop(control);
+});
@@ -0,0 +1,53 @@
+#![cfg(all(feature = "mio", unix))]
+
+extern crate mio;
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use mio::{Events, Interest, Poll, Token};
+use zmq::ReadinessSource;
+
+test!(test_readiness_mio, {
+    let ctx = zmq::Context::new();
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.bind("inproc://readiness-mio").unwrap();
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://readiness-mio").unwrap();
+
+    let mut poll = Poll::new().unwrap();
+    let mut source = ReadinessSource::new(pull);
+    poll.registry().register(&mut source, Token(0), Interest::READABLE).unwrap();
+
+    let sender = thread::spawn(move || {
+        for i in 0..10 {
+            push.send(&format!("{}", i), 0).unwrap();
+            thread::sleep(Duration::from_millis(5));
+        }
+    });
+
+    let mut events = Events::with_capacity(4);
+    let mut received = Vec::new();
+    // Drain once up front, since messages may have arrived before
+    // registration.
+    while received.len() < 10 {
+        source.drain_ready(zmq::POLLIN, |_| {
+            let msg = try!(source.get_ref().recv_string(zmq::DONTWAIT));
+            received.push(msg.unwrap());
+            Ok(())
+        }).unwrap();
+        if received.len() < 10 {
+            poll.poll(&mut events, Some(Duration::from_secs(5))).unwrap();
+            assert!(!events.is_empty());
+        }
+    }
+    let expected: Vec<_> = (0..10).map(|i| format!("{}", i)).collect();
+    assert_eq!(received, expected);
+
+    poll.registry().deregister(&mut source).unwrap();
+    sender.join().unwrap();
+});
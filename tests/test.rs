@@ -6,6 +6,7 @@ mod common;
 
 use std::io;
 use std::net::TcpStream;
+use std::time::Duration;
 use zmq::*;
 
 fn create_socketpair() -> (Socket, Socket) {
@@ -83,6 +84,88 @@ test!(test_exchanging_multipart, {
     assert_eq!(&msg2[..], b"bar");
 });
 
+test!(test_exchanging_static, {
+    static HEARTBEAT: &'static [u8] = b"HEARTBEAT";
+
+    let (sender, receiver) = create_socketpair();
+    sender.send_static(HEARTBEAT, 0).unwrap();
+    assert_eq!(receiver.recv_bytes(0).unwrap(), HEARTBEAT);
+
+    let mut msg = Message::from_static(HEARTBEAT);
+    assert_eq!(&msg[..], HEARTBEAT);
+    receiver.send(Message::from_static(HEARTBEAT), 0).unwrap();
+    sender.recv(&mut msg, 0).unwrap();
+    assert_eq!(&msg[..], HEARTBEAT);
+
+    // Mutation must not touch the static data.
+    let mut msg = Message::from_static(HEARTBEAT);
+    msg[0] = b'h';
+    assert_eq!(&msg[..], b"hEARTBEAT");
+    assert_eq!(HEARTBEAT, b"HEARTBEAT");
+});
+
+test!(test_exchanging_vectored, {
+    use std::io::{IoSlice, IoSliceMut};
+
+    let (sender, receiver) = create_socketpair();
+    let parts = [IoSlice::new(b"foo"), IoSlice::new(b"bar")];
+    sender.send_vectored(&parts, 0).unwrap();
+
+    let mut buf1 = [0u8; 8];
+    let mut buf2 = [0u8; 8];
+    {
+        let mut bufs = [IoSliceMut::new(&mut buf1), IoSliceMut::new(&mut buf2)];
+        let result = receiver.recv_vectored(&mut bufs, 0).unwrap();
+        assert_eq!(result.sizes, vec![3, 3]);
+        assert!(!result.truncated);
+    }
+    assert_eq!(&buf1[..3], b"foo");
+    assert_eq!(&buf2[..3], b"bar");
+
+    assert_eq!(receiver.send_vectored::<&[u8]>(&[], 0).unwrap_err(), Error::EINVAL);
+});
+
+test!(test_recv_vectored_truncation, {
+    let (sender, receiver) = create_socketpair();
+    sender.send_vectored(&[&b"a quite long part"[..], b"x", b"y"], 0).unwrap();
+
+    let mut buf1 = [0u8; 4];
+    let mut buf2 = [0u8; 4];
+    {
+        let mut bufs = [&mut buf1[..], &mut buf2[..]];
+        let result = receiver.recv_vectored(&mut bufs, 0).unwrap();
+        assert_eq!(result.parts(), 3);
+        assert_eq!(result.sizes, vec![17, 1, 1]);
+        assert!(result.truncated);
+    }
+    assert_eq!(&buf1, b"a qu");
+    assert_eq!(&buf2[..1], b"x");
+
+    // The part without a buffer has been discarded, rather than left
+    // in the socket, so the reply can be sent and the next request
+    // starts with its first part.
+    receiver.send("reply", 0).unwrap();
+    assert_eq!(sender.recv_bytes(0).unwrap(), b"reply");
+    sender.send_vectored(&[&b"next"[..], b"z"], 0).unwrap();
+    assert_eq!(receiver.recv_multipart(0).unwrap(), vec![b"next".to_vec(), b"z".to_vec()]);
+});
+
+test!(test_message_metadata, {
+    let (sender, receiver) = create_socketpair();
+    sender.send("foo", 0).unwrap();
+    let msg = receiver.recv_msg(0).unwrap();
+
+    assert_eq!(msg.peer_socket_type(), Some(REQ));
+    assert_eq!(msg.gets(PROPERTY_SOCKET_TYPE), Some("REQ"));
+    assert_eq!(msg.peer_address(), Some("127.0.0.1"));
+    assert_eq!(msg.app_property("Missing"), None);
+    assert_eq!(msg.gets("Invalid\0Property"), None);
+
+    let names: Vec<&str> = msg.properties().map(|(name, _)| name).collect();
+    assert!(names.contains(&PROPERTY_SOCKET_TYPE));
+    assert!(names.contains(&PROPERTY_PEER_ADDRESS));
+});
+
 test!(test_polling, {
     let (sender, receiver) = create_socketpair();
 
@@ -96,6 +179,50 @@ test!(test_polling, {
     assert_eq!(poll_items[0].get_revents(), POLLIN);
 });
 
+test!(test_poller, {
+    let (sender, receiver) = create_socketpair();
+    let (other_sender, other_receiver) = create_socketpair();
+    let mut poller = Poller::new().unwrap();
+    poller.add_socket(&receiver, 1, POLLIN).unwrap();
+    poller.add_socket(&other_receiver, 2, POLLIN).unwrap();
+    assert_eq!(poller.len(), 2);
+
+    // no message yet
+    assert_eq!(poller.wait(100).unwrap().count(), 0);
+
+    other_sender.send("Hello!", 0).unwrap();
+    let events: Vec<_> = poller.wait(1000).unwrap().collect();
+    assert_eq!(events, vec![(2, POLLIN)]);
+
+    // after removing the registration, the pending message is ignored
+    poller.remove(2).unwrap();
+    assert_eq!(poller.wait(100).unwrap().count(), 0);
+
+    sender.send("Hello!", 0).unwrap();
+    let events: Vec<_> = poller.wait(1000).unwrap().collect();
+    assert_eq!(events, vec![(1, POLLIN)]);
+
+    // once the request has been received, the reply can be sent
+    poller.modify(1, POLLIN | POLLOUT).unwrap();
+    receiver.recv_bytes(0).unwrap();
+    let events: Vec<_> = poller.wait(1000).unwrap().collect();
+    assert_eq!(events, vec![(1, POLLOUT)]);
+});
+
+test!(test_poller_invalid, {
+    let (_, receiver) = create_socketpair();
+    let (_, other_receiver) = create_socketpair();
+    let mut poller = Poller::new().unwrap();
+    poller.add_socket(&receiver, 1, POLLIN).unwrap();
+    assert_eq!(poller.add_socket(&receiver, 2, POLLIN), Err(Error::EINVAL));
+    assert_eq!(poller.add_socket(&other_receiver, 1, POLLIN), Err(Error::EINVAL));
+    assert_eq!(poller.modify(2, POLLIN), Err(Error::EINVAL));
+    assert_eq!(poller.remove(2), Err(Error::EINVAL));
+    poller.remove(1).unwrap();
+    assert!(poller.is_empty());
+    assert_eq!(poller.wait(0).unwrap().count(), 0);
+});
+
 test!(test_raw_roundtrip, {
     let ctx = Context::new();
     let mut sock = ctx.socket(SocketType::REQ).unwrap();
@@ -168,14 +295,66 @@ test!(test_get_socket_type, {
     }
 });
 
+#[cfg(ZMQ_HAS_DRAFT = "1")]
+test!(test_get_draft_socket_type, {
+    let ctx = Context::new();
+
+    let mut socket_types = vec![
+        SocketType::SERVER,
+        SocketType::CLIENT,
+        SocketType::RADIO,
+        SocketType::DISH,
+        SocketType::GATHER,
+        SocketType::SCATTER,
+        SocketType::DGRAM,
+    ];
+    for sock_type in socket_types.drain(..) {
+        let sock = ctx.socket(sock_type).unwrap();
+        assert_eq!(sock.get_socket_type().unwrap(), sock_type);
+    }
+});
+
 test!(test_create_stream_socket, {
     let ctx = Context::new();
     let sock = ctx.socket(STREAM).unwrap();
     assert!(sock.bind("tcp://127.0.0.1:*").is_ok());
-    let ep = sock.get_last_endpoint().unwrap().unwrap();
-    let tcp = "tcp://";
-    assert!(ep.starts_with(tcp));
-    assert!(TcpStream::connect(&ep[tcp.len()..]).is_ok());
+    match sock.get_last_endpoint().unwrap().unwrap() {
+        Endpoint::Tcp { host, port: Port::Number(port), source: None } => {
+            assert!(TcpStream::connect((&host[..], port)).is_ok());
+        }
+        ep => panic!("unexpected endpoint: {}", ep),
+    }
+});
+
+test!(test_ctx_getset_io_threads, {
+    let ctx = Context::new();
+    ctx.set_io_threads(4).unwrap();
+    assert_eq!(ctx.get_io_threads().unwrap(), 4);
+});
+
+test!(test_ctx_getset_max_sockets, {
+    let ctx = Context::new();
+    ctx.set_max_sockets(100).unwrap();
+    assert_eq!(ctx.get_max_sockets().unwrap(), 100);
+    assert!(ctx.get_socket_limit().unwrap() >= 100);
+});
+
+test!(test_ctx_getset_ipv6, {
+    let ctx = Context::new();
+    ctx.set_ipv6(true).unwrap();
+    assert!(ctx.is_ipv6().unwrap());
+
+    let sock = ctx.socket(REQ).unwrap();
+    assert!(sock.is_ipv6().unwrap());
+});
+
+test!(test_ctx_getset_blocky, {
+    let ctx = Context::new();
+    ctx.set_blocky(false).unwrap();
+    assert!(!ctx.is_blocky().unwrap());
+
+    let sock = ctx.socket(REQ).unwrap();
+    assert_eq!(sock.get_linger().unwrap(), 0);
 });
 
 test!(test_getset_maxmsgsize, {
@@ -454,6 +633,127 @@ test!(test_ctx_nohang, {
     assert_eq!(sock.get_socket_type(), Ok(REQ));
 });
 
+test!(test_socket_close, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PUSH).unwrap();
+    sock.connect("tcp://127.0.0.1:1").unwrap();
+    sock.send("pending", DONTWAIT).unwrap();
+    sock.close_with_linger(Duration::from_millis(0)).unwrap();
+
+    // With all sockets closed, the context terminates right away.
+    ctx.socket(PULL).unwrap().close().unwrap();
+    assert_eq!(ctx.term_with_timeout(Duration::from_secs(1)), Ok(Ok(())));
+});
+
+test!(test_drain_ready, {
+    let ctx = Context::new();
+    let receiver = ctx.socket(PULL).unwrap();
+    receiver.bind("inproc://drain-ready").unwrap();
+    let sender = ctx.socket(PUSH).unwrap();
+    sender.connect("inproc://drain-ready").unwrap();
+    for i in 0..5 {
+        sender.send(&format!("{}", i), 0).unwrap();
+    }
+
+    let mut received = Vec::new();
+    receiver.drain_ready(POLLIN, |ready| {
+        assert_eq!(ready, POLLIN);
+        received.push(try!(receiver.recv_bytes(DONTWAIT)));
+        Ok(())
+    }).unwrap();
+    assert_eq!(received.len(), 5);
+    assert!(!receiver.get_events().unwrap().contains(POLLIN));
+
+    // Errors other than `EAGAIN` are passed on.
+    sender.send("more", 0).unwrap();
+    assert_eq!(receiver.drain_ready(POLLIN, |_| Err(Error::EFSM)), Err(Error::EFSM));
+
+    // `EAGAIN` ends the loop, even though the socket is still ready.
+    let mut calls = 0;
+    receiver.drain_ready(POLLIN, |_| {
+        calls += 1;
+        Err(Error::EAGAIN)
+    }).unwrap();
+    assert_eq!(calls, 1);
+    assert!(receiver.get_events().unwrap().contains(POLLIN));
+});
+
+test!(test_drain_ready_pollout, {
+    let ctx = Context::new();
+    let receiver = ctx.socket(PULL).unwrap();
+    receiver.bind("inproc://drain-ready-pollout").unwrap();
+    let sender = ctx.socket(PUSH).unwrap();
+    sender.connect("inproc://drain-ready-pollout").unwrap();
+
+    // The socket stays writable after the queue has been sent, so the
+    // handler signals that it is done with `EAGAIN`.
+    let mut queue: Vec<String> = (0..5).map(|i| format!("{}", i)).collect();
+    queue.reverse();
+    sender.drain_ready(POLLOUT, |ready| {
+        assert_eq!(ready, POLLOUT);
+        match queue.pop() {
+            Some(msg) => sender.send(&msg, DONTWAIT),
+            None => Err(Error::EAGAIN),
+        }
+    }).unwrap();
+    assert!(queue.is_empty());
+    assert!(sender.get_events().unwrap().contains(POLLOUT));
+    for i in 0..5 {
+        assert_eq!(receiver.recv_string(0).unwrap().unwrap(), format!("{}", i));
+    }
+});
+
+test!(test_ctx_shutdown_handle, {
+    let ctx = Context::new();
+    let handle = ctx.shutdown_handle();
+    let sock = ctx.socket(PULL).unwrap();
+    sock.bind("inproc://shutdown").unwrap();
+
+    let thread = std::thread::spawn(move || {
+        let result = sock.recv_msg(0);
+        drop(sock);
+        result
+    });
+    handle.clone().shutdown().unwrap();
+    assert_eq!(thread.join().unwrap().unwrap_err(), Error::ETERM);
+    assert_eq!(ctx.socket(PUSH).err(), Some(Error::ETERM));
+
+    drop(ctx);
+    // The handle does not keep the context alive.
+    handle.shutdown().unwrap();
+});
+
+test!(test_ctx_term_with_timeout, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+
+    let open = ctx.term_with_timeout(Duration::from_millis(10)).unwrap();
+    assert_eq!(open, Err(vec![REQ]));
+
+    drop(sock);
+    ctx.term_with_timeout(Duration::from_millis(10)).unwrap().unwrap();
+    assert_eq!(ctx.socket(REQ).err(), Some(Error::ETERM));
+});
+
+test!(test_ctx_concurrent_term, {
+    let ctx = Context::new();
+    let threads: Vec<_> = (0..4).map(|i| {
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            if i % 2 == 0 {
+                ctx.term_with_timeout(Duration::from_secs(1)).unwrap().unwrap();
+            } else {
+                while ctx.get_io_threads().is_ok() {}
+                assert_eq!(ctx.get_io_threads(), Err(Error::ETERM));
+            }
+        })
+    }).collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(ctx.set_io_threads(2), Err(Error::ETERM));
+});
+
 #[cfg(ZMQ_HAS_CURVE = "1")]
 test!(test_getset_curve_server, {
     let ctx = Context::new();
@@ -510,7 +810,58 @@ test!(test_disconnect, {
 test!(test_disconnect_err, {
     let (sender, _) = create_socketpair();
     // Check that disconnect propagates errors. The endpoint is not connected.
-    assert_eq!(Error::ENOENT, sender.disconnect("tcp://192.0.2.1:2233").unwrap_err());
+    let err = sender.disconnect("tcp://192.0.2.1:2233").unwrap_err();
+    assert_eq!(Error::ENOENT, err.error);
+    assert_eq!(err.operation, "disconnect");
+    assert_eq!(err.endpoint, Some("tcp://192.0.2.1:2233".to_owned()));
+});
+
+test!(test_unbind, {
+    let (sender, receiver) = create_socketpair();
+    let ep = receiver.get_last_endpoint().unwrap().unwrap();
+    assert_eq!(receiver.bound_endpoints(), vec![ep.to_string()]);
+    assert_eq!(sender.connected_endpoints(), vec![ep.to_string()]);
+
+    receiver.unbind(&ep).unwrap();
+    assert!(receiver.bound_endpoints().is_empty());
+    assert_eq!(Error::ENOENT, receiver.unbind(&ep).unwrap_err().error);
+
+    // Bind to a fresh port, as the previous listener may still be
+    // closing in the background.
+    receiver.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = receiver.get_last_endpoint().unwrap().unwrap();
+    assert_eq!(receiver.bound_endpoints(), vec![ep.to_string()]);
+
+    // Unbinding via another spelling of the bound address also removes
+    // the endpoint from the list. The shorthand `127.1` is resolved
+    // without consulting DNS.
+    let port = match ep {
+        Endpoint::Tcp { port: Port::Number(port), .. } => port,
+        ref ep => panic!("unexpected endpoint: {}", ep),
+    };
+    receiver.unbind(&format!("tcp://127.1:{}", port)).unwrap();
+    assert!(receiver.bound_endpoints().is_empty());
+});
+
+test!(test_unbind_disconnect_all, {
+    let ctx = Context::new();
+    let server = ctx.socket(PULL).unwrap();
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let endpoints = server.bound_endpoints();
+    assert_eq!(endpoints.len(), 2);
+    assert!(endpoints.iter().all(|ep| !ep.ends_with(":*")));
+
+    let client = ctx.socket(PUSH).unwrap();
+    for ep in &endpoints {
+        client.connect(ep).unwrap();
+    }
+    assert_eq!(client.connected_endpoints(), endpoints);
+
+    client.disconnect_all().unwrap();
+    assert!(client.connected_endpoints().is_empty());
+    server.unbind_all().unwrap();
+    assert!(server.bound_endpoints().is_empty());
 });
 
 #[cfg(ZMQ_HAS_GSSAPI = "1")]
@@ -553,6 +904,223 @@ test!(test_getset_handshake_ivl, {
     assert_eq!(sock.get_handshake_ivl().unwrap(), 50000);
 });
 
+test!(test_set_pattern_options, {
+    let ctx = Context::new();
+    assert!(ctx.socket(XPUB).unwrap().set_xpub_verbose(true).is_ok());
+    assert!(ctx.socket(ROUTER).unwrap().set_router_raw(true).is_ok());
+    let req = ctx.socket(REQ).unwrap();
+    assert!(req.set_req_correlate(true).is_ok());
+    assert!(req.set_req_relaxed(true).is_ok());
+});
+
+#[cfg(ZMQ_VERSION_4_1 = "1")]
+test!(test_set_router_options, {
+    let ctx = Context::new();
+    let sock = ctx.socket(ROUTER).unwrap();
+    assert!(sock.set_router_handover(true).is_ok());
+    assert!(sock.set_connect_rid(b"peer").is_ok());
+});
+
+#[cfg(ZMQ_VERSION_4_2 = "1")]
+test!(test_getset_heartbeat, {
+    let ctx = Context::new();
+    let sock = ctx.socket(DEALER).unwrap();
+    sock.set_heartbeat_ivl(1000).unwrap();
+    assert_eq!(sock.get_heartbeat_ivl().unwrap(), 1000);
+    sock.set_heartbeat_ttl(3000).unwrap();
+    assert_eq!(sock.get_heartbeat_ttl().unwrap(), 3000);
+    sock.set_heartbeat_timeout(2000).unwrap();
+    assert_eq!(sock.get_heartbeat_timeout().unwrap(), 2000);
+});
+
+#[cfg(ZMQ_VERSION_4_2 = "1")]
+test!(test_getset_connect_timeout, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    sock.set_connect_timeout(5000).unwrap();
+    assert_eq!(sock.get_connect_timeout().unwrap(), 5000);
+    sock.set_tcp_maxrt(10000).unwrap();
+    assert_eq!(sock.get_tcp_maxrt().unwrap(), 10000);
+});
+
+#[cfg(ZMQ_VERSION_4_2 = "1")]
+test!(test_getset_multicast_maxtpdu, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PUB).unwrap();
+    sock.set_multicast_maxtpdu(1400).unwrap();
+    assert_eq!(sock.get_multicast_maxtpdu().unwrap(), 1400);
+});
+
+#[cfg(ZMQ_VERSION_4_2 = "1")]
+test!(test_xpub_options, {
+    let ctx = Context::new();
+    let xpub = ctx.socket(XPUB).unwrap();
+    xpub.set_xpub_manual(true).unwrap();
+    xpub.set_xpub_welcome_msg(b"welcome").unwrap();
+    xpub.set_invert_matching(true).unwrap();
+    assert!(xpub.is_invert_matching().unwrap());
+    let stream = ctx.socket(STREAM).unwrap();
+    stream.set_stream_notify(false).unwrap();
+});
+
+#[cfg(ZMQ_VERSION_4_2 = "1")]
+test!(test_xpub_welcome_msg, {
+    let ctx = Context::new();
+    let xpub = ctx.socket(XPUB).unwrap();
+    xpub.set_xpub_welcome_msg(b"welcome").unwrap();
+    xpub.bind("inproc://welcome").unwrap();
+    xpub.set_rcvtimeo(1000).unwrap();
+    let sub = ctx.socket(SUB).unwrap();
+    sub.set_rcvtimeo(1000).unwrap();
+    sub.set_subscribe(b"").unwrap();
+    sub.connect("inproc://welcome").unwrap();
+    // The XPUB socket only attaches the subscriber, sending the
+    // welcome message, when processing its commands.
+    assert_eq!(xpub.recv_bytes(0).unwrap(), b"\x01");
+    assert_eq!(sub.recv_bytes(0).unwrap(), b"welcome");
+});
+
+#[cfg(ZMQ_VERSION_4_3 = "1")]
+test!(test_getset_bindtodevice, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    // Binding to a device requires privileges, but the option value
+    // itself can be set regardless.
+    sock.set_bindtodevice("lo").unwrap();
+    assert_eq!(sock.get_bindtodevice().unwrap(), Ok("lo".to_owned()));
+});
+
+#[cfg(all(ZMQ_VERSION_4_3 = "1", ZMQ_HAS_DRAFT = "1"))]
+test!(test_draft_options, {
+    let ctx = Context::new();
+    let router = ctx.socket(ROUTER).unwrap();
+    router.set_router_notify(3).unwrap();
+    assert_eq!(router.get_router_notify().unwrap(), 3);
+    router.set_zap_enforce_domain(true).unwrap();
+    assert!(router.is_zap_enforce_domain().unwrap());
+    router.set_loopback_fastpath(true).unwrap();
+    router.set_metadata("X-Service:telemetry").unwrap();
+});
+
+test!(test_socket_options_apply, {
+    let ctx = Context::new();
+    let sock = ctx.socket(DEALER).unwrap();
+    let options = SocketOptions {
+        linger: Some(0),
+        sndhwm: Some(42),
+        identity: Some(b"worker-1".to_vec()),
+        zap_domain: Some("global".to_owned()),
+        ..SocketOptions::default()
+    };
+    sock.apply(&options).unwrap();
+    assert_eq!(sock.get_linger().unwrap(), 0);
+    assert_eq!(sock.get_sndhwm().unwrap(), 42);
+    assert_eq!(sock.get_identity().unwrap(), b"worker-1");
+
+    let snapshot = sock.options().unwrap();
+    assert_eq!(snapshot.linger, Some(0));
+    assert_eq!(snapshot.sndhwm, Some(42));
+    assert_eq!(snapshot.rcvhwm, Some(sock.get_rcvhwm().unwrap()));
+    assert_eq!(snapshot.identity, Some(b"worker-1".to_vec()));
+    assert_eq!(snapshot.zap_domain, Some("global".to_owned()));
+    // The default heartbeat timeout cannot be set explicitly.
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    assert_eq!(snapshot.heartbeat_timeout, None);
+
+    // A snapshot can be applied to another socket.
+    let other = ctx.socket(DEALER).unwrap();
+    other.apply(&snapshot).unwrap();
+    assert_eq!(other.options().unwrap(), snapshot);
+});
+
+test!(test_socket_options_mechanism, {
+    let ctx = Context::new();
+    let sock = ctx.socket(ROUTER).unwrap();
+    sock.set_plain_server(true).unwrap();
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    sock.set_invert_matching(true).unwrap();
+
+    let snapshot = sock.options().unwrap();
+    assert_eq!(snapshot.plain_server, Some(true));
+    // Disabled mechanisms are omitted, so applying the snapshot does
+    // not reset the PLAIN mechanism.
+    #[cfg(ZMQ_HAS_CURVE = "1")]
+    assert_eq!(snapshot.curve_server, None);
+    #[cfg(ZMQ_VERSION_4_2 = "1")]
+    assert_eq!(snapshot.invert_matching, Some(true));
+    #[cfg(ZMQ_VERSION_4_3 = "1")]
+    assert_eq!(snapshot.bindtodevice, None);
+
+    let other = ctx.socket(ROUTER).unwrap();
+    other.apply(&snapshot).unwrap();
+    assert_eq!(other.get_mechanism().unwrap(), zmq::Mechanism::ZMQ_PLAIN);
+    assert_eq!(other.options().unwrap(), snapshot);
+});
+
+test!(test_socket_options_error, {
+    let ctx = Context::new();
+    let sock = ctx.socket(DEALER).unwrap();
+    let options = SocketOptions {
+        sndhwm: Some(42),
+        linger: Some(-2),
+        rcvtimeo: Some(100),
+        ..SocketOptions::default()
+    };
+    let err = sock.apply(&options).unwrap_err();
+    assert_eq!(err.option, "linger");
+    assert_eq!(err.error, Error::EINVAL);
+    // Options are applied in declaration order, up to the failing one.
+    assert_eq!(sock.get_sndhwm().unwrap(), 42);
+    assert_eq!(sock.get_rcvtimeo().unwrap(), -1);
+});
+
+test!(test_getset_durations, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+
+    assert_eq!(sock.get_rcvtimeo_duration().unwrap(), None);
+    sock.set_rcvtimeo_duration(Some(Duration::from_millis(1500))).unwrap();
+    assert_eq!(sock.get_rcvtimeo().unwrap(), 1500);
+    assert_eq!(sock.get_rcvtimeo_duration().unwrap(), Some(Duration::from_millis(1500)));
+    sock.set_rcvtimeo_duration(None).unwrap();
+    assert_eq!(sock.get_rcvtimeo().unwrap(), -1);
+
+    // Sub-millisecond durations are rounded up, rather than turning
+    // into a non-blocking zero timeout.
+    sock.set_sndtimeo_duration(Some(Duration::new(0, 1))).unwrap();
+    assert_eq!(sock.get_sndtimeo().unwrap(), 1);
+
+    sock.set_linger_duration(Some(Duration::from_secs(2))).unwrap();
+    assert_eq!(sock.get_linger().unwrap(), 2000);
+
+    // Keepalive options are counted in seconds.
+    sock.set_tcp_keepalive_idle_duration(Some(Duration::from_secs(30))).unwrap();
+    assert_eq!(sock.get_tcp_keepalive_idle().unwrap(), 30);
+    assert_eq!(sock.get_tcp_keepalive_intvl_duration().unwrap(), None);
+
+    sock.set_handshake_ivl_duration(None).unwrap();
+    assert_eq!(sock.get_handshake_ivl().unwrap(), 0);
+    assert_eq!(sock.get_handshake_ivl_duration().unwrap(), None);
+
+    sock.set_recovery_ivl_duration(Duration::from_secs(5)).unwrap();
+    assert_eq!(sock.get_recovery_ivl_duration().unwrap(), Duration::from_secs(5));
+
+    assert_eq!(sock.set_rcvtimeo_duration(Some(Duration::from_secs(u64::max_value()))),
+               Err(Error::EINVAL));
+});
+
+test!(test_poll_duration, {
+    let (sender, receiver) = create_socketpair();
+    assert_eq!(receiver.poll_duration(POLLIN, Some(Duration::from_millis(10))).unwrap(), 0);
+    sender.send("Hello!", 0).unwrap();
+    let mut poll_items = vec![receiver.as_poll_item(POLLIN)];
+    assert_eq!(poll_duration(&mut poll_items, None).unwrap(), 1);
+
+    let mut poller = Poller::new().unwrap();
+    poller.add_socket(&receiver, 0, POLLIN).unwrap();
+    assert_eq!(poller.wait_duration(Some(Duration::from_secs(1))).unwrap().count(), 1);
+});
+
 #[cfg(feature = "compiletest_rs")]
 mod compile {
     extern crate compiletest_rs as compiletest;
@@ -0,0 +1,71 @@
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use std::cell::Cell;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use zmq::Timers;
+
+test!(test_timers_execute, {
+    let fired = Cell::new(0);
+    let mut timers = Timers::new();
+    assert_eq!(timers.timeout(), -1);
+    assert_eq!(timers.timeout_duration(), None);
+
+    let id = timers.add(Duration::from_millis(50), |_| fired.set(fired.get() + 1));
+    let timeout = timers.timeout();
+    assert!(timeout > 0 && timeout <= 50);
+    assert!(timers.timeout_duration().unwrap() <= Duration::from_millis(50));
+    assert_eq!(timers.execute(), 0);
+
+    thread::sleep(Duration::from_millis(60));
+    assert_eq!(timers.timeout(), 0);
+    assert_eq!(timers.timeout_duration(), Some(Duration::from_secs(0)));
+    assert_eq!(timers.execute(), 1);
+    assert_eq!(fired.get(), 1);
+
+    // The timer repeats.
+    assert!(timers.timeout() > 0);
+    thread::sleep(Duration::from_millis(60));
+    assert_eq!(timers.execute(), 1);
+    assert_eq!(fired.get(), 2);
+
+    timers.cancel(id).unwrap();
+    assert!(timers.is_empty());
+    assert_eq!(timers.cancel(id), Err(zmq::Error::EINVAL));
+});
+
+test!(test_timers_reset, {
+    let mut timers = Timers::new();
+    let id = timers.add(Duration::from_millis(50), |_| panic!("timer should not fire"));
+    for _ in 0..3 {
+        thread::sleep(Duration::from_millis(20));
+        timers.reset(id).unwrap();
+        assert_eq!(timers.execute(), 0);
+    }
+    timers.set_interval(id, Duration::from_secs(60)).unwrap();
+    assert!(timers.timeout() > 50_000);
+    assert_eq!(timers.reset(id + 1), Err(zmq::Error::EINVAL));
+    assert_eq!(timers.set_interval(id + 1, Duration::from_secs(1)), Err(zmq::Error::EINVAL));
+});
+
+test!(test_timers_poll, {
+    let ctx = zmq::Context::new();
+    let socket = ctx.socket(zmq::PULL).unwrap();
+    socket.bind("inproc://timers").unwrap();
+
+    let fired = Cell::new(None);
+    let mut timers = Timers::new();
+    let id = timers.add(Duration::from_millis(20), |id| fired.set(Some(id)));
+    let start = Instant::now();
+    while fired.get().is_none() {
+        let mut items = [socket.as_poll_item(zmq::POLLIN)];
+        assert_eq!(zmq::poll(&mut items, timers.timeout()).unwrap(), 0);
+        timers.execute();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert_eq!(fired.get(), Some(id));
+});
@@ -0,0 +1,74 @@
+extern crate zmq;
+
+#[macro_use]
+mod common;
+
+use std::thread;
+
+use zmq::{Context, Pull, Push, Rep, Req, Sub, Pub, TypedSocket};
+
+test!(test_typed_req_rep, {
+    let ctx = Context::new();
+    let rep: TypedSocket<Rep> = ctx.typed_socket().unwrap();
+    rep.bind("inproc://typed-req-rep").unwrap();
+    let mut req = ctx.typed_socket::<Req>().unwrap();
+    req.connect("inproc://typed-req-rep").unwrap();
+
+    let server = thread::spawn(move || {
+        let mut rep = rep;
+        for _ in 0..3 {
+            let (replying, request) = rep.recv_multipart(0).unwrap();
+            assert_eq!(request, vec![b"ping".to_vec()]);
+            rep = replying.send("pong", 0).unwrap();
+        }
+    });
+
+    for _ in 0..3 {
+        let awaiting = req.send("ping", 0).unwrap();
+        let (ready, reply) = awaiting.recv_multipart(0).unwrap();
+        assert_eq!(reply, vec![b"pong".to_vec()]);
+        req = ready;
+    }
+    server.join().unwrap();
+});
+
+test!(test_typed_transition_error, {
+    let ctx = Context::new();
+    let req = ctx.typed_socket::<Req>().unwrap();
+    req.connect("inproc://typed-nobody").unwrap();
+    let awaiting = req.send("ping", 0).unwrap();
+
+    // A failed receive hands back the socket in its previous state.
+    let err = awaiting.recv_multipart(zmq::DONTWAIT).err().unwrap();
+    assert_eq!(err.error, zmq::Error::EAGAIN);
+    let awaiting = err.socket;
+    assert_eq!(awaiting.recv_multipart(zmq::DONTWAIT).err().unwrap().error,
+               zmq::Error::EAGAIN);
+});
+
+test!(test_typed_push_pull, {
+    let ctx = Context::new();
+    let pull = ctx.typed_socket::<Pull>().unwrap();
+    pull.bind("inproc://typed-push-pull").unwrap();
+    let push = ctx.typed_socket::<Push>().unwrap();
+    push.connect("inproc://typed-push-pull").unwrap();
+
+    push.send_multipart(vec!["a", "b"], 0).unwrap();
+    assert_eq!(pull.recv_multipart(0).unwrap(), vec![b"a".to_vec(), b"b".to_vec()]);
+});
+
+test!(test_typed_pub_sub, {
+    let ctx = Context::new();
+    let publisher = ctx.typed_socket::<Pub>().unwrap();
+    publisher.bind("inproc://typed-pub-sub").unwrap();
+    let subscriber = ctx.typed_socket::<Sub>().unwrap();
+    subscriber.set_subscribe(b"topic").unwrap();
+    subscriber.connect("inproc://typed-pub-sub").unwrap();
+
+    publisher.send("other", 0).unwrap();
+    publisher.send("topic", 0).unwrap();
+    assert_eq!(subscriber.recv_bytes(0).unwrap(), b"topic");
+
+    let socket = subscriber.into_inner();
+    assert_eq!(socket.get_socket_type().unwrap(), zmq::SUB);
+});
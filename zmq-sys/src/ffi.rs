@@ -30,7 +30,35 @@ impl ::std::default::Default for Struct_zmq_pollitem_t {
     fn default() -> Self { unsafe { ::std::mem::zeroed() } }
 }
 pub type zmq_pollitem_t = Struct_zmq_pollitem_t;
-pub enum Struct_iovec { }
+#[repr(C)]
+#[derive(Copy)]
+pub struct Struct_iovec {
+    pub iov_base: *mut ::std::os::raw::c_void,
+    pub iov_len: size_t,
+}
+impl ::std::clone::Clone for Struct_iovec {
+    fn clone(&self) -> Self { *self }
+}
+pub type iovec = Struct_iovec;
+#[cfg(feature = "draft")]
+#[repr(C)]
+#[derive(Copy)]
+pub struct Struct_zmq_poller_event_t {
+    pub socket: *mut ::std::os::raw::c_void,
+    pub fd: ::std::os::raw::c_int,
+    pub user_data: *mut ::std::os::raw::c_void,
+    pub events: ::std::os::raw::c_short,
+}
+#[cfg(feature = "draft")]
+impl ::std::clone::Clone for Struct_zmq_poller_event_t {
+    fn clone(&self) -> Self { *self }
+}
+#[cfg(feature = "draft")]
+impl ::std::default::Default for Struct_zmq_poller_event_t {
+    fn default() -> Self { unsafe { ::std::mem::zeroed() } }
+}
+#[cfg(feature = "draft")]
+pub type zmq_poller_event_t = Struct_zmq_poller_event_t;
 pub type zmq_thread_fn =
     unsafe extern "C" fn(arg1: *mut ::std::os::raw::c_void);
 #[link(name = "zmq")]
@@ -172,3 +200,55 @@ extern "C" {
      -> *mut ::std::os::raw::c_void;
     pub fn zmq_threadclose(thread: *mut ::std::os::raw::c_void);
 }
+
+// Draft API, only available if libzmq was built with it enabled.
+#[cfg(feature = "draft")]
+#[link(name = "zmq")]
+extern "C" {
+    pub fn zmq_msg_set_routing_id(msg: *mut zmq_msg_t, routing_id: uint32_t)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_msg_routing_id(msg: *mut zmq_msg_t) -> uint32_t;
+    pub fn zmq_msg_set_group(msg: *mut zmq_msg_t,
+                             group: *const ::std::os::raw::c_char)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_msg_group(msg: *mut zmq_msg_t)
+     -> *const ::std::os::raw::c_char;
+    pub fn zmq_join(s: *mut ::std::os::raw::c_void,
+                    group: *const ::std::os::raw::c_char)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_leave(s: *mut ::std::os::raw::c_void,
+                     group: *const ::std::os::raw::c_char)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_new() -> *mut ::std::os::raw::c_void;
+    pub fn zmq_poller_destroy(poller_p: *mut *mut ::std::os::raw::c_void)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_add(poller: *mut ::std::os::raw::c_void,
+                          socket: *mut ::std::os::raw::c_void,
+                          user_data: *mut ::std::os::raw::c_void,
+                          events: ::std::os::raw::c_short)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_modify(poller: *mut ::std::os::raw::c_void,
+                             socket: *mut ::std::os::raw::c_void,
+                             events: ::std::os::raw::c_short)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_remove(poller: *mut ::std::os::raw::c_void,
+                             socket: *mut ::std::os::raw::c_void)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_add_fd(poller: *mut ::std::os::raw::c_void,
+                             fd: ::std::os::raw::c_int,
+                             user_data: *mut ::std::os::raw::c_void,
+                             events: ::std::os::raw::c_short)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_modify_fd(poller: *mut ::std::os::raw::c_void,
+                                fd: ::std::os::raw::c_int,
+                                events: ::std::os::raw::c_short)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_remove_fd(poller: *mut ::std::os::raw::c_void,
+                                fd: ::std::os::raw::c_int)
+     -> ::std::os::raw::c_int;
+    pub fn zmq_poller_wait_all(poller: *mut ::std::os::raw::c_void,
+                               events: *mut zmq_poller_event_t,
+                               n_events: ::std::os::raw::c_int,
+                               timeout: ::std::os::raw::c_long)
+     -> ::std::os::raw::c_int;
+}
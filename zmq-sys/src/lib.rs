@@ -20,6 +20,7 @@ pub use ffi::{
     zmq_msg_t,
     zmq_free_fn,
     zmq_pollitem_t,
+    iovec,
     zmq_version,
     zmq_errno,
     zmq_strerror,
@@ -76,12 +77,35 @@ pub use ffi::{
     zmq_threadclose,
 };
 
+// Draft API
+#[cfg(feature = "draft")]
+pub use ffi::{
+    zmq_poller_event_t,
+    zmq_msg_set_routing_id,
+    zmq_msg_routing_id,
+    zmq_msg_set_group,
+    zmq_msg_group,
+    zmq_join,
+    zmq_leave,
+    zmq_poller_new,
+    zmq_poller_destroy,
+    zmq_poller_add,
+    zmq_poller_modify,
+    zmq_poller_remove,
+    zmq_poller_add_fd,
+    zmq_poller_modify_fd,
+    zmq_poller_remove_fd,
+    zmq_poller_wait_all,
+};
+
 #[allow(non_camel_case_types)]
 mod ffi {
     use libc::{
         uint8_t,
         size_t,
     };
+    #[cfg(feature = "draft")]
+    use libc::uint32_t;
 
     include!("ffi.rs");
 }